@@ -0,0 +1,38 @@
+//! 記録している IP の数を増やしても、1 リクエストあたりの判定時間が変わらないことを確かめる
+//!
+//! `cargo bench --bench ratelimiter`
+
+use backend::utils::ratelimiter::{RateLimitConfig, RateLimiter};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+const REQUESTS: u32 = 100_000;
+
+fn ip(n: u32) -> IpAddr {
+    IpAddr::from((0x0a00_0000 + n).to_be_bytes())
+}
+
+async fn per_request(tracked: u32) -> Duration {
+    let limiter = RateLimiter::new(RateLimitConfig::default());
+    let now = Instant::now();
+    for n in 0..tracked {
+        limiter.check_at(ip(n), "/", now).await;
+    }
+    assert_eq!(limiter.tracked(), tracked as usize);
+
+    let start = Instant::now();
+    for n in 0..REQUESTS {
+        let status = limiter.check_at(ip(n % tracked), "/", now).await;
+        std::hint::black_box(status);
+    }
+
+    start.elapsed() / REQUESTS
+}
+
+#[tokio::main]
+async fn main() {
+    for tracked in [100, 1_000, 10_000, 100_000] {
+        let cost = per_request(tracked).await;
+        println!("{:>7} tracked IPs: {:>6} ns/request", tracked, cost.as_nanos());
+    }
+}
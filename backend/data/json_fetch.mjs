@@ -45,12 +45,32 @@ async function writeJson(filename, data) {
   }));
 
   const problemModels = Object.fromEntries(
-    problems.map(({ id }) => [
-      id,
-      { difficulty: problemModelsData[id]?.difficulty ?? null },
-    ])
+    problems.map(({ id }) => {
+      const model = problemModelsData[id] ?? {};
+      return [
+        id,
+        {
+          difficulty: model.difficulty ?? null,
+          slope: model.slope,
+          intercept: model.intercept,
+          variance: model.variance,
+          discrimination: model.discrimination,
+          is_experimental: model.is_experimental,
+          irt_loglikelihood: model.irt_loglikelihood,
+        },
+      ];
+    })
   );
 
+  // 解ける確率での絞り込みに使うため、discrimination のないデータは保存しない
+  const withDiscrimination = Object.values(problemModels).filter(
+    ({ discrimination }) => typeof discrimination === "number"
+  ).length;
+  if (withDiscrimination === 0) {
+    throw new Error(`No problem model has discrimination in ${endpoints.problemModels}`);
+  }
+  console.log(`Models with discrimination: ${withDiscrimination}/${problems.length}`);
+
   await writeJson("problems.json", problems);
   await writeJson("problem-models.json", problemModels);
 })();
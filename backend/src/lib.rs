@@ -1 +1,7 @@
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+
+pub use utils::error::{ApiError, ErrorCode};
+pub use utils::i18n::{Lang, Message};
+pub use utils::problem_query::{
+    Contest, ProblemQuery, ProblemQueryBuilder, QueryError, UnknownDifficulty,
+};
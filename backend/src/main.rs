@@ -1,21 +1,126 @@
-use backend::utils::api;
-use backend::utils::ratelimiter::RateLimiter;
-use backend::utils::routing::{AppState, router};
+use backend::utils::admin::{admin_router, AdminConfig};
+use backend::utils::api::DataSource;
+use backend::utils::client_ip::ClientIpResolver;
+use backend::utils::daily::DailyProblems;
+use backend::utils::i18n::Lang;
+use backend::utils::ratelimiter::{RateLimitConfig, RateLimiter};
+use backend::utils::refresh::{refresh_data, RefreshOptions};
+use backend::utils::reload::{load_snapshot, DataReloader, SharedState};
+use backend::utils::routing::{router_with_services, Services};
+use backend::utils::saved::SavedProblems;
+use backend::utils::session::SessionStore;
+use backend::utils::storage::storage_from_env;
+use backend::utils::submissions::SolvedProblems;
 
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{Server, Body, Response, StatusCode};
-use std::sync::Arc;
+use hyper::Server;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::convert::Infallible;
+use std::time::Duration;
+
+async fn refresh_command(args: &[String]) {
+    let options = match RefreshOptions::from_args(args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("usage: backend refresh-data [--source <base-url>] [--out <dir>]");
+            std::process::exit(2);
+        }
+    };
+
+    match refresh_data(&options).await {
+        Ok(summary) => {
+            println!("Succeeded to refresh {}", options.out.display());
+            summary.print();
+        }
+        Err(e) => {
+            eprintln!("Failed to refresh problems: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    match api::fetch_problem().await {
-        Ok((problems, problem_models)) => {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("refresh-data") {
+        return refresh_command(&args[1..]).await;
+    }
+
+    let source = DataSource::from_env();
+
+    match load_snapshot(&source).await {
+        Ok((state, info)) => {
             println!("Succeeded to fetch problems");
 
-            let state = Arc::new(AppState { problems, problem_models });
-            let limiter = RateLimiter::new();
+            let state = SharedState::with_info(state, info);
+            let limiter = match RateLimitConfig::from_env() {
+                Ok(config) => RateLimiter::new(config),
+                Err(e) => {
+                    eprintln!("Invalid rate limit: {}", e);
+                    std::process::exit(2);
+                }
+            };
+            limiter.spawn_eviction(Duration::from_secs(60));
+            let client_ips = match ClientIpResolver::from_env() {
+                Ok(client_ips) => Arc::new(client_ips),
+                Err(e) => {
+                    eprintln!("Invalid trusted proxies: {}", e);
+                    std::process::exit(2);
+                }
+            };
+            let session_retention = match SessionStore::retention_from_env() {
+                Ok(retention) => retention,
+                Err(e) => {
+                    eprintln!("Invalid session retention: {}", e);
+                    std::process::exit(2);
+                }
+            };
+            let admin = Arc::new(AdminConfig::from_env(source.clone()));
+            let services = Services {
+                solved_problems: match SolvedProblems::from_env() {
+                    Ok(solved_problems) => Some(Arc::new(solved_problems)),
+                    Err(e) => {
+                        eprintln!("Warning: 'user' parameter is disabled: {}", e);
+                        None
+                    }
+                },
+                sessions: match SessionStore::with_storage(
+                    session_retention,
+                    storage_from_env(&source.dir, "sessions"),
+                ) {
+                    Ok(sessions) => Arc::new(sessions),
+                    Err(e) => {
+                        eprintln!("Warning: sessions will not be persisted: {}", e);
+                        Arc::new(SessionStore::new(session_retention))
+                    }
+                },
+                daily_problems: match DailyProblems::with_storage(storage_from_env(&source.dir, "daily")) {
+                    Ok(daily_problems) => Arc::new(daily_problems),
+                    Err(e) => {
+                        eprintln!("Warning: daily problems will not be persisted: {}", e);
+                        Arc::default()
+                    }
+                },
+                saved_problems: match SavedProblems::with_storage(storage_from_env(&source.dir, "saved")) {
+                    Ok(saved_problems) => Arc::new(saved_problems),
+                    Err(e) => {
+                        eprintln!("Warning: saved problems will not be persisted: {}", e);
+                        Arc::default()
+                    }
+                },
+            };
+
+            // データファイルの更新を定期的に確認する (0 で無効)
+            let reload_interval: u64 = std::env::var("DATA_RELOAD_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("DATA_RELOAD_INTERVAL_SECS must be a number");
+            if reload_interval > 0 {
+                DataReloader::new(source, state.clone())
+                    .spawn(Duration::from_secs(reload_interval));
+            }
 
             // Fly.io 環境変数 PORT を使用
             let port: u16 = std::env::var("PORT")
@@ -30,20 +135,35 @@ async fn main() {
                 let remote_addr = conn.remote_addr().ip();
                 let state = state.clone();
                 let limiter = limiter.clone();
+                let client_ips = client_ips.clone();
+                let admin = admin.clone();
+                let services = services.clone();
 
                 async move {
                     Ok::<_, Infallible>(service_fn(move |req| {
                         let state = state.clone();
                         let limiter = limiter.clone();
-                        let ip = remote_addr;
+                        let admin = admin.clone();
+                        let services = services.clone();
+                        let ip = client_ips.resolve(remote_addr, req.headers());
 
                         async move {
-                            if !limiter.check(ip).await {
-                                let mut res = Response::new(Body::from("Too Many Requests"));
-                                *res.status_mut() = StatusCode::TOO_MANY_REQUESTS;
-                                return Ok::<_, Infallible>(res)
+                            // CORS のプリフライトは制限の対象にしない
+                            if req.method() == hyper::Method::OPTIONS {
+                                return router_with_services(req, state.load(), services).await;
                             }
-                            router(req, state).await
+
+                            let status = limiter.check(ip, req.uri().path()).await;
+                            if !status.allowed {
+                                return Ok::<_, Infallible>(status.rejected_response(Lang::from_request(&req)));
+                            }
+                            let mut res = if req.uri().path().starts_with("/admin/") {
+                                admin_router(req, state, admin).await?
+                            } else {
+                                router_with_services(req, state.load(), services).await?
+                            };
+                            status.apply_headers(res.headers_mut());
+                            Ok(res)
                         }
                     }))
                 }
@@ -59,6 +179,9 @@ async fn main() {
                 println!("server shut down.");
             }
         }
-        Err(e) => eprintln!("Failed to fetch problems: {}", e),
+        Err(e) => {
+            eprintln!("Failed to fetch problems: {}", e);
+            std::process::exit(1);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,96 @@
+use chrono::Local;
+use hyper::{header, Body, Request, Response, StatusCode};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::utils::api::DataSource;
+use crate::utils::error::{ApiError, ErrorCode};
+use crate::utils::i18n::{Lang, Message};
+use crate::utils::reload::{SharedState, SnapshotInfo};
+use crate::utils::routing::{json_response, log};
+
+pub struct AdminConfig {
+    /// 未設定の場合は管理用エンドポイントを無効にする
+    pub token: Option<String>,
+    pub source: DataSource,
+}
+
+impl AdminConfig {
+    pub fn from_env(source: DataSource) -> Self {
+        Self {
+            token: std::env::var("ADMIN_TOKEN")
+                .ok()
+                .filter(|token| !token.trim().is_empty()),
+            source,
+        }
+    }
+
+    fn is_authorized(&self, req: &Request<Body>) -> bool {
+        let Some(token) = &self.token else {
+            return false;
+        };
+
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+    }
+}
+
+#[derive(Serialize)]
+struct ReloadResponse {
+    previous_problem_count: usize,
+    status: SnapshotInfo,
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// `/admin/` 以下のリクエストを処理する
+pub async fn admin_router(
+    req: Request<Body>,
+    state: SharedState,
+    config: Arc<AdminConfig>,
+) -> Result<Response<Body>, Infallible> {
+    let now = Local::now();
+    let path = req.uri().path().to_string();
+    let method = req.method().to_string();
+    let lang = Lang::from_request(&req);
+
+    let res = if config.token.is_none() {
+        ApiError::new(ErrorCode::NotFound, Message::NotFound).into_response(lang)
+    } else if !config.is_authorized(&req) {
+        ApiError::new(ErrorCode::InvalidAdminToken, Message::InvalidAdminToken).into_response(lang)
+    } else {
+        match (req.method(), req.uri().path()) {
+            (&hyper::Method::GET, "/admin/status") => json_response(StatusCode::OK, &state.info()),
+
+            (&hyper::Method::POST, "/admin/reload") => {
+                let previous_problem_count = state.info().problem_count;
+
+                match state.reload_from(&config.source).await {
+                    Ok(status) => json_response(
+                        StatusCode::OK,
+                        &ReloadResponse {
+                            previous_problem_count,
+                            status,
+                        },
+                    ),
+                    Err(e) => {
+                        eprintln!("Failed to reload problems: {}", e);
+                        ApiError::new(ErrorCode::InternalError, Message::ReloadFailed).into_response(lang)
+                    }
+                }
+            }
+
+            _ => ApiError::new(ErrorCode::NotFound, Message::NotFound).into_response(lang),
+        }
+    };
+
+    log(now, &method, &path, res.status());
+
+    Ok(res)
+}
@@ -1,26 +1,67 @@
 use std::option::Option::None;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::string::ToString;
 use std::{env, fs};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Problem {
     pub id: String,
     pub contest_id: String,
     pub name: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ProblemModelRaw {
     pub difficulty: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slope: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intercept: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discrimination: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_experimental: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub irt_loglikelihood: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ProblemModel {
     pub difficulty: Option<f64>,
+    pub slope: Option<f64>,
+    pub intercept: Option<f64>,
+    pub variance: Option<f64>,
+    pub discrimination: Option<f64>,
+    pub is_experimental: Option<bool>,
+    pub irt_loglikelihood: Option<f64>,
+}
+
+impl From<ProblemModelRaw> for ProblemModel {
+    fn from(raw: ProblemModelRaw) -> Self {
+        Self {
+            difficulty: adjust_difficulty(raw.difficulty),
+            slope: raw.slope,
+            intercept: raw.intercept,
+            variance: raw.variance,
+            discrimination: raw.discrimination,
+            is_experimental: raw.is_experimental,
+            irt_loglikelihood: raw.irt_loglikelihood,
+        }
+    }
+}
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// 実行環境によって値が変わらない 64bit FNV-1a ハッシュ
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
 }
 
 fn adjust_difficulty(difficulty: Option<i32>) -> Option<f64> {
@@ -31,13 +72,64 @@ fn adjust_difficulty(difficulty: Option<i32>) -> Option<f64> {
     }
 }
 
-pub async fn fetch_problem() -> Result<(Vec<Problem>, HashMap<String, ProblemModel>), Box<dyn Error + Send + Sync>> {
+/// adjust_difficulty の逆変換。表示上の値から内部的な値に戻す
+fn unadjust_difficulty(adjusted: f64) -> f64 {
+    if adjusted >= 400.0 {
+        adjusted
+    } else {
+        400.0 * (1.0 - f64::ln(400.0 / adjusted))
+    }
+}
+
+/// 表示上のレーティングが rating のユーザーがこの問題を解く確率の予測値
+pub fn predict_solve_probability(model: &ProblemModel, rating: f64) -> Option<f64> {
+    let difficulty = unadjust_difficulty(model.difficulty?);
+    let discrimination = model.discrimination?;
+
+    Some(1.0 / (1.0 + f64::exp(-discrimination * (unadjust_difficulty(rating) - difficulty))))
+}
+
+pub fn data_dir() -> PathBuf {
     let data_dir = env::var("DATA_DIR").unwrap_or_else(|_| "data".to_string());
-    
-    let base_path = PathBuf::from(data_dir);
 
-    let problems_path = base_path.join("problems.json");
-    let problem_models_path = base_path.join("problem-models.json");
+    PathBuf::from(data_dir)
+}
+
+/// データファイルの置き場所と、読み込み時に厳密な検証を行うかどうか
+#[derive(Debug, Clone)]
+pub struct DataSource {
+    pub dir: PathBuf,
+    pub strict: bool,
+}
+
+impl DataSource {
+    /// STRICT_DATA_VALIDATION が未設定の場合、リリースビルドでは厳密モードにする
+    pub fn from_env() -> Self {
+        let strict = match env::var("STRICT_DATA_VALIDATION") {
+            Ok(value) => matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"),
+            Err(_) => !cfg!(debug_assertions),
+        };
+
+        Self {
+            dir: data_dir(),
+            strict,
+        }
+    }
+}
+
+pub fn data_file_paths(base_path: &Path) -> (PathBuf, PathBuf) {
+    (
+        base_path.join("problems.json"),
+        base_path.join("problem-models.json"),
+    )
+}
+
+pub async fn fetch_problem() -> Result<(Vec<Problem>, HashMap<String, ProblemModel>), Box<dyn Error + Send + Sync>> {
+    fetch_problem_from(&data_dir()).await
+}
+
+pub async fn fetch_problem_from(base_path: &Path) -> Result<(Vec<Problem>, HashMap<String, ProblemModel>), Box<dyn Error + Send + Sync>> {
+    let (problems_path, problem_models_path) = data_file_paths(base_path);
 
     // ファイルが存在しない場合に落ちないようにする
     if !problems_path.exists() {
@@ -58,14 +150,7 @@ pub async fn fetch_problem() -> Result<(Vec<Problem>, HashMap<String, ProblemMod
 
     let problem_models: HashMap<String, ProblemModel> = raw_models
         .into_iter()
-        .map(|(id, raw)| {
-            (
-                id,
-                ProblemModel {
-                    difficulty: adjust_difficulty(raw.difficulty),
-                },
-            )
-        })
+        .map(|(id, raw)| (id, ProblemModel::from(raw)))
         .collect();
 
     Ok((problems, problem_models))
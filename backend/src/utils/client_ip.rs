@@ -0,0 +1,170 @@
+use hyper::header::{HeaderMap, FORWARDED};
+use std::net::{IpAddr, Ipv6Addr};
+
+const FLY_CLIENT_IP: &str = "fly-client-ip";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+// IPv6 は 1 契約に /64 が割り当てられることが多いので、まとめて 1 クライアントとみなす
+const IPV6_CLIENT_PREFIX: u8 = 64;
+
+/// `10.0.0.0/8` や `fdaa::/16` のようなアドレス範囲
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    /// プレフィックス長を省略した場合は単一のアドレスとして扱う
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("invalid address: {}", value))?
+            .to_canonical();
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| format!("invalid prefix length: {}", value))?,
+            None => max_prefix,
+        };
+
+        Ok(Self {
+            addr: mask(addr, prefix),
+            prefix,
+        })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        ip.is_ipv4() == self.addr.is_ipv4() && mask(ip, self.prefix) == self.addr
+    }
+}
+
+fn mask(ip: IpAddr, prefix: u8) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let bits = u32::from(ip) & u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::from(bits.to_be_bytes())
+        }
+        IpAddr::V6(ip) => {
+            let bits = u128::from(ip) & u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(bits))
+        }
+    }
+}
+
+/// レート制限のキー。IPv6 は /64 単位にまとめる
+pub fn rate_limit_key(ip: IpAddr) -> IpAddr {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => IpAddr::V4(ip),
+        ip => mask(ip, IPV6_CLIENT_PREFIX),
+    }
+}
+
+/// 信頼するプロキシからの接続に限り、転送ヘッダーからクライアントの IP を求める
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl ClientIpResolver {
+    pub fn new(trusted_proxies: Vec<IpNet>) -> Self {
+        Self { trusted_proxies }
+    }
+
+    /// `TRUSTED_PROXIES` (例: `172.16.0.0/12,fdaa::/16`) から読む。未設定ならヘッダーは使わない
+    pub fn from_env() -> Result<Self, String> {
+        let trusted_proxies = match std::env::var("TRUSTED_PROXIES") {
+            Ok(value) => value
+                .split(',')
+                .filter(|net| !net.trim().is_empty())
+                .map(IpNet::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("TRUSTED_PROXIES: {}", e))?,
+            Err(_) => Vec::new(),
+        };
+
+        Ok(Self::new(trusted_proxies))
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(ip))
+    }
+
+    /// `Fly-Client-IP`、`X-Forwarded-For`、`Forwarded` の順に見る。
+    /// 接続元が信頼するプロキシでなければ、ヘッダーは偽装できるので無視する
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = peer.to_canonical();
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        let fly_client_ip = headers
+            .get(FLY_CLIENT_IP)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok());
+
+        fly_client_ip
+            .map(|ip| ip.to_canonical())
+            .or_else(|| {
+                let hops = header_values(headers, X_FORWARDED_FOR)
+                    .flat_map(|value| value.split(','))
+                    .map(|hop| hop.trim().parse::<IpAddr>().ok())
+                    .collect::<Vec<_>>();
+                self.nearest_client(&hops)
+            })
+            .or_else(|| {
+                let hops = header_values(headers, FORWARDED.as_str())
+                    .flat_map(|value| value.split(','))
+                    .map(forwarded_for)
+                    .collect::<Vec<_>>();
+                self.nearest_client(&hops)
+            })
+            .unwrap_or(peer)
+    }
+
+    // 右端 (自分に近い側) から信頼するプロキシを飛ばし、最初の信頼しないアドレスを返す。
+    // 読めない値があればそこから先は偽装されうるので、ヘッダーを使わない
+    fn nearest_client(&self, hops: &[Option<IpAddr>]) -> Option<IpAddr> {
+        let mut client = None;
+        for hop in hops.iter().rev() {
+            let hop = (*hop)?.to_canonical();
+            client = Some(hop);
+            if !self.is_trusted(hop) {
+                break;
+            }
+        }
+
+        client
+    }
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+}
+
+/// `Forwarded` の 1 要素から `for=` のアドレスを読む (`for="[2001:db8::1]:443"` など)
+fn forwarded_for(element: &str) -> Option<IpAddr> {
+    let value = element.split(';').find_map(|pair| {
+        let (key, value) = pair.trim().split_once('=')?;
+        key.trim().eq_ignore_ascii_case("for").then(|| value.trim().trim_matches('"'))
+    })?;
+
+    if let Some(rest) = value.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.rsplit_once(':')?.0.parse().ok())
+}
@@ -0,0 +1,163 @@
+use chrono::{Duration, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::utils::storage::{sha256_hex, MemoryStorage, Record, Storage};
+
+/// 出題記録を残す日数。これより前や未来の日付の日替わり問題は記録しない
+pub const RETENTION_DAYS: i64 = 30;
+/// 記録の上限。絞り込み条件の組み合わせで際限なく増えないようにする
+pub const MAX_RECORDS: usize = 10_000;
+// 上限に達したときに、日付の古い順に取り除く件数
+const EVICTION_BATCH: usize = MAX_RECORDS / 10;
+
+struct DailyRecords {
+    // キーは絞り込み条件のハッシュ
+    problems: HashMap<String, (NaiveDate, String)>,
+    // 最後に古い記録を取り除いた日
+    pruned_on: NaiveDate,
+}
+
+impl DailyRecords {
+    fn to_records(&self) -> Vec<Record> {
+        self.problems
+            .iter()
+            .map(|(key, (date, problem_id))| Record::DailyProblem {
+                date: *date,
+                key: key.clone(),
+                problem_id: problem_id.clone(),
+            })
+            .collect()
+    }
+}
+
+/// 日替わり問題として出題した問題を記録し、データ更新や再起動をまたいで同じ問題を返す
+pub struct DailyProblems {
+    storage: Arc<dyn Storage>,
+    records: Mutex<DailyRecords>,
+}
+
+impl Default for DailyProblems {
+    fn default() -> Self {
+        Self {
+            storage: Arc::new(MemoryStorage::new()),
+            records: Mutex::new(DailyRecords {
+                problems: HashMap::new(),
+                pruned_on: Utc::now().date_naive(),
+            }),
+        }
+    }
+}
+
+// 任意の長さのキーを保存しないよう、固定長のハッシュにする
+fn hash_key(key: &str) -> String {
+    sha256_hex(key.as_bytes())
+}
+
+impl DailyProblems {
+    /// 保存済みの記録を読み込み、古い記録を取り除く
+    pub fn with_storage(storage: Arc<dyn Storage>) -> io::Result<Self> {
+        let problems = storage
+            .load()?
+            .into_iter()
+            .filter_map(|record| match record {
+                Record::DailyProblem {
+                    date,
+                    key,
+                    problem_id,
+                } => Some((key, (date, problem_id))),
+                _ => None,
+            })
+            .collect();
+
+        let daily_problems = Self {
+            storage,
+            records: Mutex::new(DailyRecords {
+                problems,
+                pruned_on: NaiveDate::MIN,
+            }),
+        };
+        daily_problems.prune(Utc::now().date_naive())?;
+
+        Ok(daily_problems)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.records
+            .lock()
+            .unwrap()
+            .problems
+            .get(&hash_key(key))
+            .map(|(_, problem_id)| problem_id.clone())
+    }
+
+    /// 初めて出題した問題を記録する。すでに記録がある場合は書き換えない。
+    /// 上限に達している場合は、日付の古い記録から取り除いてから記録する
+    pub fn record(&self, date: NaiveDate, key: &str, problem_id: &str) {
+        let today = Utc::now().date_naive();
+        // 時差があるため翌日までは今日として扱う
+        if date < today - Duration::days(RETENTION_DAYS) || date > today + Duration::days(1) {
+            return;
+        }
+
+        if self.records.lock().unwrap().pruned_on < today {
+            if let Err(e) = self.prune(today) {
+                eprintln!("Failed to prune daily problems: {}", e);
+            }
+        }
+
+        let key = hash_key(key);
+        let mut records = self.records.lock().unwrap();
+        if records.problems.contains_key(&key) {
+            return;
+        }
+
+        let evicts = records.problems.len() >= MAX_RECORDS;
+        if evicts {
+            let mut oldest = records
+                .problems
+                .iter()
+                .map(|(key, (date, _))| (*date, key.clone()))
+                .collect::<Vec<_>>();
+            oldest.sort();
+            for (_, key) in oldest.into_iter().take(EVICTION_BATCH) {
+                records.problems.remove(&key);
+            }
+            eprintln!(
+                "Daily problem records reached {}; evicted the {} oldest",
+                MAX_RECORDS, EVICTION_BATCH
+            );
+        }
+        records
+            .problems
+            .insert(key.clone(), (date, problem_id.to_string()));
+
+        let result = if evicts {
+            let remaining = records.to_records();
+            drop(records);
+            self.storage.rewrite(&remaining)
+        } else {
+            drop(records);
+            self.storage.append(&Record::DailyProblem {
+                date,
+                key,
+                problem_id: problem_id.to_string(),
+            })
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to persist daily problem: {}", e);
+        }
+    }
+
+    /// `today` から `RETENTION_DAYS` 日より前の記録を取り除き、保存先も書き直す
+    pub fn prune(&self, today: NaiveDate) -> io::Result<()> {
+        let oldest = today - Duration::days(RETENTION_DAYS);
+        let mut records = self.records.lock().unwrap();
+        records.problems.retain(|_, (date, _)| *date >= oldest);
+        records.pruned_on = today;
+
+        let remaining = records.to_records();
+        self.storage.rewrite(&remaining)
+    }
+}
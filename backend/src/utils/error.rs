@@ -0,0 +1,168 @@
+use hyper::{header, Body, Response, StatusCode};
+use serde::Serialize;
+
+use crate::utils::i18n::{Lang, Message};
+use crate::utils::problem_query::QueryError;
+use crate::utils::routing::with_cors_headers;
+
+/// エラーの種類。レスポンスの `code` としてそのまま返す
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidNumber,
+    InvalidInteger,
+    InvalidBoolean,
+    InvalidValue,
+    OutOfRange,
+    MinGreaterThanMax,
+    MissingParameter,
+    TooManyValues,
+    InvalidExcludeId,
+    InvalidProblemId,
+    UnknownField,
+    UnsupportedParameter,
+    InvalidBody,
+    BodyTooLarge,
+    MissingUserToken,
+    InvalidUserToken,
+    InvalidAdminToken,
+    NotFound,
+    ProblemNotFound,
+    SessionNotFound,
+    NotSaved,
+    NoCandidates,
+    ExcludedAll,
+    SolvedAll,
+    NotEnoughCandidates,
+    MethodNotAllowed,
+    SavedLimitExceeded,
+    SessionLimitExceeded,
+    RateLimited,
+    InternalError,
+    UpstreamUnavailable,
+    ModelUnavailable,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::MissingUserToken | ErrorCode::InvalidUserToken | ErrorCode::InvalidAdminToken => {
+                StatusCode::UNAUTHORIZED
+            }
+            ErrorCode::NotFound
+            | ErrorCode::ProblemNotFound
+            | ErrorCode::SessionNotFound
+            | ErrorCode::NotSaved
+            | ErrorCode::NoCandidates
+            | ErrorCode::ExcludedAll
+            | ErrorCode::SolvedAll
+            | ErrorCode::NotEnoughCandidates => StatusCode::NOT_FOUND,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::SavedLimitExceeded => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ErrorCode::UpstreamUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::ModelUnavailable | ErrorCode::SessionLimitExceeded => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// サーバーが返すエラー。`param` は原因になったパラメータや JSON の項目
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub param: Option<String>,
+    pub message: Message,
+}
+
+/// すべてのエラーレスポンスの本文
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    param: Option<&'a str>,
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: Message) -> Self {
+        Self {
+            code,
+            param: None,
+            message,
+        }
+    }
+
+    /// パラメータの値に問題がある場合のエラー
+    pub fn invalid(code: ErrorCode, param: impl Into<String>, message: Message) -> Self {
+        Self {
+            code,
+            param: Some(param.into()),
+            message,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    pub fn localized_message(&self, lang: Lang) -> String {
+        self.message
+            .text(self.param.as_deref().unwrap_or_default(), lang)
+    }
+
+    pub fn into_response(self, lang: Lang) -> Response<Body> {
+        let body = ErrorBody {
+            code: self.code,
+            param: self.param.as_deref(),
+            message: self.localized_message(lang),
+        };
+        let mut res = Response::new(Body::from(serde_json::to_string(&body).unwrap()));
+        *res.status_mut() = self.status();
+        res.headers_mut()
+            .insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+
+        with_cors_headers(res)
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.localized_message(Lang::default()))
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<QueryError> for ApiError {
+    fn from(error: QueryError) -> Self {
+        let (code, param, message) = match error {
+            QueryError::MinGreaterThanMax => (
+                ErrorCode::MinGreaterThanMax,
+                "min",
+                Message::GreaterThan("max".to_string()),
+            ),
+            QueryError::NegativeMin => (ErrorCode::OutOfRange, "min", Message::LessThanZero),
+            QueryError::RoundRangeReversed => (
+                ErrorCode::MinGreaterThanMax,
+                "contest_from",
+                Message::GreaterThan("contest_to".to_string()),
+            ),
+            QueryError::NegativeRating => (ErrorCode::OutOfRange, "rating", Message::LessThanZero),
+            QueryError::ProbabilityMinOutOfRange => {
+                (ErrorCode::OutOfRange, "p_min", Message::ProbabilityRange)
+            }
+            QueryError::ProbabilityMaxOutOfRange => {
+                (ErrorCode::OutOfRange, "p_max", Message::ProbabilityRange)
+            }
+            QueryError::ProbabilityRangeReversed => (
+                ErrorCode::MinGreaterThanMax,
+                "p_min",
+                Message::GreaterThan("p_max".to_string()),
+            ),
+        };
+
+        ApiError::invalid(code, param, message)
+    }
+}
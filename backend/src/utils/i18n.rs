@@ -0,0 +1,328 @@
+use hyper::{header, Body, Request};
+
+/// エラーメッセージの言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Lang {
+    /// `ja` や `en-US` のような言語タグを読む。対応していない言語は `None`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        if primary.eq_ignore_ascii_case("ja") {
+            Some(Lang::Ja)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Lang::En)
+        } else {
+            None
+        }
+    }
+
+    /// `Accept-Language` のうち、対応している言語で最も q 値が高いものを選ぶ
+    pub fn from_accept_language(value: &str) -> Option<Self> {
+        let mut best: Option<(Lang, f32)> = None;
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let Some(lang) = parts.next().and_then(Lang::from_tag) else {
+                continue;
+            };
+            let quality = parts
+                .find_map(|part| part.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok());
+            let Some(quality) = quality.filter(|q| *q > 0.0) else {
+                continue;
+            };
+            // 同じ q 値なら先に書かれたものを優先する
+            if best.is_none_or(|(_, best)| quality > best) {
+                best = Some((lang, quality));
+            }
+        }
+
+        best.map(|(lang, _)| lang)
+    }
+
+    /// `lang` パラメータ、`Accept-Language` の順に見て、どちらもなければ日本語にする
+    pub fn from_request(req: &Request<Body>) -> Self {
+        let query = req.uri().query().unwrap_or("");
+        let from_param = url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "lang")
+            .and_then(|(_, value)| Lang::from_tag(&value));
+
+        from_param
+            .or_else(|| {
+                req.headers()
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(Lang::from_accept_language)
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// エラーメッセージのカタログ。`'{param}'` を含む文面はエラーの `param` を埋め込む
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    NotANumber,
+    NotAPositiveInteger,
+    NotANonNegativeInteger,
+    NotABoolean,
+    NotAString,
+    NotAnArray,
+    NotAnObject,
+    EmptyString,
+    UnknownField,
+    UnknownOptionalField(String),
+    OneOf(String),
+    Required,
+    RatingRequired,
+    Between(u64, u64),
+    ProbabilityRange,
+    LessThanZero,
+    GreaterThan(String),
+    TooManyProblemIds(usize),
+    TooManyRanges(usize),
+    TooManyTags(usize),
+    TooManyContests(usize),
+    ContestTooLong(usize),
+    TooDeep(usize),
+    InvalidProblemId,
+    InvalidExcludeId,
+    InvalidSort,
+    InvalidCursor,
+    InvalidBands,
+    InvalidUser,
+    UnsupportedUser,
+    InvalidSession,
+    InvalidTimeZone,
+    InvalidDate,
+    InvalidJson,
+    BodyNotObject,
+    InvalidSaveBody,
+    BodyReadFailed,
+    BodyTooLarge(usize),
+    NoteTooLong(usize),
+    TagLength(usize),
+    MissingUserToken,
+    InvalidUserToken,
+    InvalidAdminToken,
+    NotFound,
+    MethodNotAllowed,
+    ProblemNotFound,
+    SessionNotFound,
+    NotSaved,
+    SavedLimitExceeded(usize),
+    TooManyUserTokens,
+    TooManySessions,
+    NoCandidates,
+    SolveProbabilityUnavailable,
+    NoCandidatesInBand(f64, f64),
+    ExcludedAll,
+    SolvedAll,
+    NotEnoughCandidates { found: usize, requested: usize },
+    SubmissionsUnavailable,
+    ReloadFailed,
+    RateLimited,
+}
+
+impl Message {
+    pub fn text(&self, param: &str, lang: Lang) -> String {
+        match lang {
+            Lang::Ja => self.ja(param),
+            Lang::En => self.en(param),
+        }
+    }
+
+    fn en(&self, p: &str) -> String {
+        match self {
+            Message::NotANumber => format!("'{}' must be a number.", p),
+            Message::NotAPositiveInteger => format!("'{}' must be a positive integer.", p),
+            Message::NotANonNegativeInteger => format!("'{}' must be a non-negative integer.", p),
+            Message::NotABoolean => format!("'{}' must be true or false.", p),
+            Message::NotAString => format!("'{}' must be a string.", p),
+            Message::NotAnArray => format!("'{}' must be an array.", p),
+            Message::NotAnObject => format!("'{}' must be a JSON object.", p),
+            Message::EmptyString => format!("'{}' must be a non-empty string.", p),
+            Message::UnknownField => format!("'{}' is not a known field.", p),
+            Message::UnknownOptionalField(field) => {
+                format!("'{}' contains an unknown field '{}'.", p, field)
+            }
+            Message::OneOf(values) => format!("'{}' must be one of {}.", p, values),
+            Message::Required => format!("'{}' is required.", p),
+            Message::RatingRequired => {
+                "'rating' is required when 'p_min' or 'p_max' is specified.".to_string()
+            }
+            Message::Between(min, max) => format!("'{}' must be between {} and {}.", p, min, max),
+            Message::ProbabilityRange => "'p_min' and 'p_max' must be between 0 and 1.".to_string(),
+            Message::LessThanZero => format!("'{}' cannot be less than 0.", p),
+            Message::GreaterThan(other) => format!("'{}' cannot be greater than '{}'.", p, other),
+            Message::TooManyProblemIds(max) => {
+                format!("'{}' cannot contain more than {} problem IDs.", p, max)
+            }
+            Message::TooManyRanges(max) => format!("'{}' cannot contain more than {} ranges.", p, max),
+            Message::TooManyTags(max) => format!("'{}' cannot contain more than {} tags.", p, max),
+            Message::TooManyContests(max) => format!("'{}' cannot contain more than {} contests.", p, max),
+            Message::ContestTooLong(max) => {
+                format!("Each contest in '{}' must be at most {} characters.", p, max)
+            }
+            Message::TooDeep(max) => format!("'{}' cannot be nested more than {} levels.", p, max),
+            Message::InvalidProblemId => format!("'{}' is not a valid problem ID.", p),
+            Message::InvalidExcludeId => format!("'{}' contains an invalid problem ID.", p),
+            Message::InvalidSort => {
+                "'sort' must be one of difficulty, contest, id (prefix '-' for descending).".to_string()
+            }
+            Message::InvalidCursor => format!("'{}' is invalid.", p),
+            Message::InvalidBands => {
+                "'bands' must be a comma-separated list of ranges such as 0-400,400-800.".to_string()
+            }
+            Message::InvalidUser => "'user' must be a valid AtCoder user ID.".to_string(),
+            Message::UnsupportedUser => "'user' is not supported on this server.".to_string(),
+            Message::InvalidSession => "'session' must be a valid session ID.".to_string(),
+            Message::InvalidTimeZone => "'tz' must be a UTC offset such as +09:00.".to_string(),
+            Message::InvalidDate => "'date' must be in YYYY-MM-DD format.".to_string(),
+            Message::InvalidJson => "Request body must be valid JSON.".to_string(),
+            Message::BodyNotObject => "Request body must be a JSON object.".to_string(),
+            Message::InvalidSaveBody => {
+                "Request body must be a JSON object with optional 'note' and 'tags'.".to_string()
+            }
+            Message::BodyReadFailed => "Failed to read the request body.".to_string(),
+            Message::BodyTooLarge(max) => format!("Request body must be at most {} bytes.", max),
+            Message::NoteTooLong(max) => format!("'note' must be at most {} characters.", max),
+            Message::TagLength(max) => format!("Each tag must be between 1 and {} characters.", max),
+            Message::MissingUserToken => "A user token is required.".to_string(),
+            Message::InvalidUserToken => "Invalid user token.".to_string(),
+            Message::InvalidAdminToken => "Invalid admin token.".to_string(),
+            Message::NotFound => "404 Not Found".to_string(),
+            Message::MethodNotAllowed => "405 Method Not Allowed".to_string(),
+            Message::ProblemNotFound => "Problem not found.".to_string(),
+            Message::SessionNotFound => "Session not found. Please start a new session.".to_string(),
+            Message::NotSaved => "This problem is not saved.".to_string(),
+            Message::SavedLimitExceeded(max) => format!("You can save up to {} problems.", max),
+            Message::TooManyUserTokens => {
+                "Cannot save problems for new users right now. Try again later.".to_string()
+            }
+            Message::TooManySessions => "Cannot start a new session right now. Try again later.".to_string(),
+            Message::NoCandidates => "No problems match the difficulty range.".to_string(),
+            Message::SolveProbabilityUnavailable => {
+                "Solve probability is not available because the problem models have no discrimination.".to_string()
+            }
+            Message::NoCandidatesInBand(min, max) => {
+                format!("No problems match the difficulty range {}-{}.", min, max)
+            }
+            Message::ExcludedAll => "No problems match the conditions after excluding your history. Turn off exclusion or clear your history.".to_string(),
+            Message::SolvedAll => {
+                "No problems match the conditions after excluding solved problems.".to_string()
+            }
+            Message::NotEnoughCandidates { found, requested } => format!(
+                "Only {} problems match the conditions ({} requested).",
+                found, requested
+            ),
+            Message::SubmissionsUnavailable => {
+                "Failed to fetch submissions. Please try again later.".to_string()
+            }
+            Message::ReloadFailed => "Failed to reload problems. See the server log for details.".to_string(),
+            Message::RateLimited => "Too Many Requests".to_string(),
+        }
+    }
+
+    fn ja(&self, p: &str) -> String {
+        match self {
+            Message::NotANumber => format!("'{}' には数値を指定してください", p),
+            Message::NotAPositiveInteger => format!("'{}' には正の整数を指定してください", p),
+            Message::NotANonNegativeInteger => format!("'{}' には 0 以上の整数を指定してください", p),
+            Message::NotABoolean => format!("'{}' には true か false を指定してください", p),
+            Message::NotAString => format!("'{}' には文字列を指定してください", p),
+            Message::NotAnArray => format!("'{}' には配列を指定してください", p),
+            Message::NotAnObject => format!("'{}' には JSON オブジェクトを指定してください", p),
+            Message::EmptyString => format!("'{}' には空でない文字列を指定してください", p),
+            Message::UnknownField => format!("'{}' は不明な項目です", p),
+            Message::UnknownOptionalField(field) => {
+                format!("'{}' に不明な項目 '{}' が含まれています", p, field)
+            }
+            Message::OneOf(values) => format!("'{}' には {} のいずれかを指定してください", p, values),
+            Message::Required => format!("'{}' を指定してください", p),
+            Message::RatingRequired => {
+                "'p_min' か 'p_max' を指定する場合は 'rating' も指定してください".to_string()
+            }
+            Message::Between(min, max) => {
+                format!("'{}' には {} 以上 {} 以下の値を指定してください", p, min, max)
+            }
+            Message::ProbabilityRange => {
+                "'p_min' と 'p_max' には 0 以上 1 以下の値を指定してください".to_string()
+            }
+            Message::LessThanZero => format!("'{}' には 0 以上の値を指定してください", p),
+            Message::GreaterThan(other) => format!("'{}' は '{}' 以下にしてください", p, other),
+            Message::TooManyProblemIds(max) => {
+                format!("'{}' に指定できる問題は {} 件までです", p, max)
+            }
+            Message::TooManyRanges(max) => format!("'{}' に指定できる範囲は {} 個までです", p, max),
+            Message::TooManyTags(max) => format!("'{}' に指定できるタグは {} 個までです", p, max),
+            Message::TooManyContests(max) => format!("'{}' に指定できるコンテストは {} 個までです", p, max),
+            Message::ContestTooLong(max) => {
+                format!("'{}' のコンテストは {} 文字以内で指定してください", p, max)
+            }
+            Message::TooDeep(max) => format!("'{}' の入れ子は {} 段までです", p, max),
+            Message::InvalidProblemId => format!("'{}' は正しい問題 ID ではありません", p),
+            Message::InvalidExcludeId => format!("'{}' に正しくない問題 ID が含まれています", p),
+            Message::InvalidSort => {
+                "'sort' には difficulty, contest, id のいずれかを指定してください（先頭に '-' を付けると降順）"
+                    .to_string()
+            }
+            Message::InvalidCursor => format!("'{}' が正しくありません", p),
+            Message::InvalidBands => {
+                "'bands' には 0-400,400-800 のように範囲をカンマ区切りで指定してください".to_string()
+            }
+            Message::InvalidUser => "'user' には正しい AtCoder ユーザー ID を指定してください".to_string(),
+            Message::UnsupportedUser => "このサーバーでは 'user' を利用できません".to_string(),
+            Message::InvalidSession => "'session' には正しいセッション ID を指定してください".to_string(),
+            Message::InvalidTimeZone => "'tz' には +09:00 のような UTC オフセットを指定してください".to_string(),
+            Message::InvalidDate => "'date' は YYYY-MM-DD 形式で指定してください".to_string(),
+            Message::InvalidJson => "リクエストボディが正しい JSON ではありません".to_string(),
+            Message::BodyNotObject => "リクエストボディには JSON オブジェクトを指定してください".to_string(),
+            Message::InvalidSaveBody => {
+                "リクエストボディには 'note' と 'tags' を持つ JSON オブジェクトを指定してください".to_string()
+            }
+            Message::BodyReadFailed => "リクエストボディを読み込めませんでした".to_string(),
+            Message::BodyTooLarge(max) => format!("リクエストボディは {} バイト以内にしてください", max),
+            Message::NoteTooLong(max) => format!("'note' は {} 文字以内にしてください", max),
+            Message::TagLength(max) => format!("タグは 1 文字以上 {} 文字以内にしてください", max),
+            Message::MissingUserToken => "ユーザートークンを指定してください".to_string(),
+            Message::InvalidUserToken => "ユーザートークンが正しくありません".to_string(),
+            Message::InvalidAdminToken => "管理用トークンが正しくありません".to_string(),
+            Message::NotFound => "ページが見つかりません".to_string(),
+            Message::MethodNotAllowed => "許可されていないメソッドです".to_string(),
+            Message::ProblemNotFound => "問題が見つかりません".to_string(),
+            Message::SessionNotFound => {
+                "セッションが見つかりません。新しいセッションを発行してください".to_string()
+            }
+            Message::NotSaved => "保存されていない問題です".to_string(),
+            Message::SavedLimitExceeded(max) => format!("保存できる問題は {} 件までです", max),
+            Message::TooManyUserTokens => {
+                "現在は新しいユーザーの問題を保存できません。しばらくしてから再度お試しください".to_string()
+            }
+            Message::TooManySessions => {
+                "現在は新しいセッションを発行できません。しばらくしてから再度お試しください".to_string()
+            }
+            Message::NoCandidates => "指定Diff範囲に該当する問題がありませんでした".to_string(),
+            Message::SolveProbabilityUnavailable => {
+                "難易度モデルに識別力がないため、解ける確率では絞り込めません".to_string()
+            }
+            Message::NoCandidatesInBand(min, max) => {
+                format!("Diff {}-{} の範囲に該当する問題がありませんでした", min, max)
+            }
+            Message::ExcludedAll => "履歴内の問題を除外すると、条件に一致する問題がありません。除外をOFFにするか、履歴を削除してください".to_string(),
+            Message::SolvedAll => "解いた問題を除外すると、条件に一致する問題がありません".to_string(),
+            Message::NotEnoughCandidates { found, requested } => format!(
+                "条件に一致する問題が{}問しかありません（{}問を要求しました）",
+                found, requested
+            ),
+            Message::SubmissionsUnavailable => {
+                "提出情報を取得できませんでした。時間をおいて再度お試しください".to_string()
+            }
+            Message::ReloadFailed => "問題の再読み込みに失敗しました。詳細はサーバーのログを確認してください".to_string(),
+            Message::RateLimited => "リクエストが多すぎます。しばらく待ってから再度お試しください".to_string(),
+        }
+    }
+}
@@ -1,3 +1,18 @@
+pub mod admin;
 pub mod api;
+pub mod client_ip;
+pub mod daily;
+pub mod error;
+pub mod i18n;
+pub mod pick;
+pub mod problem_query;
+pub mod query;
 pub mod ratelimiter;
-pub mod routing;
\ No newline at end of file
+pub mod refresh;
+pub mod reload;
+pub mod routing;
+pub mod saved;
+pub mod session;
+pub mod storage;
+pub mod submissions;
+pub mod validation;
\ No newline at end of file
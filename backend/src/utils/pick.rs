@@ -0,0 +1,397 @@
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+use crate::utils::api::{Problem, ProblemModel};
+use crate::utils::error::{ApiError, ErrorCode};
+use crate::utils::i18n::Message;
+use crate::utils::problem_query::{Contest, ProblemQuery, MIN_DIFFICULTY};
+use crate::utils::query::{check_contests, is_valid_problem_id, ProblemFilter, OPTIONAL_FIELDS};
+use crate::utils::routing::{MAX_PICK_COUNT, MAX_SEED};
+
+const REQUEST_FIELDS: [&str; 7] = [
+    "count",
+    "seed",
+    "user",
+    "fields",
+    "experimental",
+    "solve_probability",
+    "exclude",
+];
+const RULE_FIELDS: [&str; 6] = [
+    "contests",
+    "contest_from",
+    "contest_to",
+    "difficulty",
+    "problems",
+    "rules",
+];
+const MAX_DIFFICULTY_RANGES: usize = 10;
+// rules の入れ子の深さの上限
+const MAX_RULE_DEPTH: usize = 3;
+
+/// `POST /pick` のリクエスト。`exclude` の件数はボディの大きさで制限する
+pub(crate) struct PickRequest {
+    pub(crate) filter: ProblemFilter,
+    pub(crate) rule: PickRule,
+    pub(crate) count: Option<usize>,
+    pub(crate) seed: Option<u64>,
+    pub(crate) user: Option<String>,
+}
+
+/// コンテスト・回・難易度の条件と、入れ子の include/exclude ルール
+pub(crate) struct PickRule {
+    /// 難易度の範囲ごとの条件。いずれかを満たせばよい
+    queries: Vec<ProblemQuery>,
+    problem_ids: HashSet<String>,
+    include: Vec<PickRule>,
+    exclude: Vec<PickRule>,
+}
+
+impl PickRule {
+    /// include があればいずれかに一致し、exclude のどれにも一致しない問題を通す
+    pub(crate) fn matches(&self, problem: &Problem, model: &ProblemModel) -> bool {
+        self.queries
+            .iter()
+            .any(|query| query.matches_conditions(problem, model))
+            && (self.problem_ids.is_empty() || self.problem_ids.contains(&problem.id))
+            && (self.include.is_empty() || self.include.iter().any(|rule| rule.matches(problem, model)))
+            && !self.exclude.iter().any(|rule| rule.matches(problem, model))
+    }
+}
+
+/// エラーに項目のパスを含めるため、JSON オブジェクトをパス付きで読む
+struct Fields<'a> {
+    path: String,
+    map: &'a Map<String, Value>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value, path: String, known: &[&str]) -> Result<Self, ApiError> {
+        let Some(map) = value.as_object() else {
+            if path.is_empty() {
+                return Err(ApiError::new(ErrorCode::InvalidBody, Message::BodyNotObject));
+            }
+            return Err(ApiError::invalid(ErrorCode::InvalidValue, path, Message::NotAnObject));
+        };
+        if let Some(key) = map.keys().find(|key| !known.contains(&key.as_str())) {
+            return Err(ApiError::invalid(
+                ErrorCode::UnknownField,
+                join_path(&path, key),
+                Message::UnknownField,
+            ));
+        }
+
+        Ok(Self { path, map })
+    }
+
+    fn path(&self, key: &str) -> String {
+        join_path(&self.path, key)
+    }
+
+    fn error(&self, code: ErrorCode, key: &str, message: Message) -> ApiError {
+        ApiError::invalid(code, self.path(key), message)
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Value> {
+        self.map.get(key).filter(|value| !value.is_null())
+    }
+
+    fn f64(&self, key: &str) -> Result<Option<f64>, ApiError> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_f64()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| self.error(ErrorCode::InvalidNumber, key, Message::NotANumber))
+            })
+            .transpose()
+    }
+
+    fn u32(&self, key: &str) -> Result<Option<u32>, ApiError> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_u64()
+                    .and_then(|value| u32::try_from(value).ok())
+                    .ok_or_else(|| self.error(ErrorCode::InvalidInteger, key, Message::NotAPositiveInteger))
+            })
+            .transpose()
+    }
+
+    fn u64(&self, key: &str) -> Result<Option<u64>, ApiError> {
+        self.get(key)
+            .map(|value| {
+                value.as_u64().ok_or_else(|| {
+                    self.error(ErrorCode::InvalidInteger, key, Message::NotANonNegativeInteger)
+                })
+            })
+            .transpose()
+    }
+
+    fn bool(&self, key: &str) -> Result<Option<bool>, ApiError> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_bool()
+                    .ok_or_else(|| self.error(ErrorCode::InvalidBoolean, key, Message::NotABoolean))
+            })
+            .transpose()
+    }
+
+    fn str(&self, key: &str) -> Result<Option<&'a str>, ApiError> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .as_str()
+                    .ok_or_else(|| self.error(ErrorCode::InvalidValue, key, Message::NotAString))
+            })
+            .transpose()
+    }
+
+    fn array(&self, key: &str) -> Result<&'a [Value], ApiError> {
+        match self.get(key) {
+            None => Ok(&[]),
+            Some(value) => value
+                .as_array()
+                .map(Vec::as_slice)
+                .ok_or_else(|| self.error(ErrorCode::InvalidValue, key, Message::NotAnArray)),
+        }
+    }
+
+    /// 空でない文字列の配列として読む
+    fn strings(&self, key: &str) -> Result<Vec<(String, &'a str)>, ApiError> {
+        self.array(key)?
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let path = format!("{}[{}]", self.path(key), index);
+                match value.as_str().map(str::trim) {
+                    Some(value) if !value.is_empty() => Ok((path, value)),
+                    _ => Err(ApiError::invalid(ErrorCode::InvalidValue, path, Message::EmptyString)),
+                }
+            })
+            .collect()
+    }
+
+    fn problem_ids(&self, key: &str, code: ErrorCode) -> Result<HashSet<String>, ApiError> {
+        self.strings(key)?
+            .into_iter()
+            .map(|(path, id)| {
+                if !is_valid_problem_id(id) {
+                    return Err(ApiError::invalid(code, path, Message::InvalidProblemId));
+                }
+                Ok(id.to_string())
+            })
+            .collect()
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+pub(crate) fn parse_pick_request(bytes: &[u8]) -> Result<PickRequest, ApiError> {
+    let value = if bytes.iter().all(u8::is_ascii_whitespace) {
+        Value::Object(Map::new())
+    } else {
+        serde_json::from_slice::<Value>(bytes)
+            .map_err(|_| ApiError::new(ErrorCode::InvalidBody, Message::InvalidJson))?
+    };
+    let known = REQUEST_FIELDS
+        .iter()
+        .chain(RULE_FIELDS.iter())
+        .copied()
+        .collect::<Vec<_>>();
+    let fields = Fields::new(&value, String::new(), &known)?;
+
+    let count = match fields.u64("count")? {
+        Some(count) if count == 0 || count > MAX_PICK_COUNT as u64 => {
+            return Err(fields.error(
+                ErrorCode::OutOfRange,
+                "count",
+                Message::Between(1, MAX_PICK_COUNT as u64),
+            ))
+        }
+        count => count.map(|count| count as usize),
+    };
+    let seed = match fields.u64("seed")? {
+        Some(seed) if seed > MAX_SEED => {
+            return Err(fields.error(
+                ErrorCode::OutOfRange,
+                "seed",
+                Message::Between(0, MAX_SEED),
+            ))
+        }
+        seed => seed,
+    };
+    let user = fields.str("user")?.map(|user| user.trim().to_string());
+
+    let mut includes_model = false;
+    for (path, field) in fields.strings("fields")? {
+        if !OPTIONAL_FIELDS.contains(&field) {
+            return Err(ApiError::invalid(
+                ErrorCode::InvalidValue,
+                path,
+                Message::OneOf(OPTIONAL_FIELDS.join(", ")),
+            ));
+        }
+        includes_model |= field == "model";
+    }
+
+    let mut builder = ProblemQuery::builder()
+        .experimental(fields.bool("experimental")?.unwrap_or(true))
+        .exclude(fields.problem_ids("exclude", ErrorCode::InvalidExcludeId)?);
+    if let Some(value) = fields.get("solve_probability") {
+        let range = Fields::new(
+            value,
+            fields.path("solve_probability"),
+            &["rating", "min", "max"],
+        )?;
+        let rating = range
+            .f64("rating")?
+            .ok_or_else(|| range.error(ErrorCode::MissingParameter, "rating", Message::Required))?;
+        let min = range.f64("min")?.unwrap_or(0.0);
+        let max = range.f64("max")?.unwrap_or(1.0);
+
+        if rating < 0.0 {
+            return Err(range.error(ErrorCode::OutOfRange, "rating", Message::LessThanZero));
+        }
+        for (key, value) in [("min", min), ("max", max)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(range.error(ErrorCode::OutOfRange, key, Message::Between(0, 1)));
+            }
+        }
+        if min > max {
+            return Err(range.error(
+                ErrorCode::MinGreaterThanMax,
+                "min",
+                Message::GreaterThan(range.path("max")),
+            ));
+        }
+        builder = builder.solve_probability(rating, min, max);
+    }
+
+    Ok(PickRequest {
+        filter: ProblemFilter {
+            query: builder.build()?,
+            includes_model,
+        },
+        rule: parse_rule(&fields, 0)?,
+        count,
+        seed,
+        user,
+    })
+}
+
+fn parse_rule(fields: &Fields, depth: usize) -> Result<PickRule, ApiError> {
+    let contest_ids = fields
+        .strings("contests")?
+        .into_iter()
+        .map(|(_, id)| id)
+        .collect::<Vec<_>>();
+    check_contests(&fields.path("contests"), &contest_ids)?;
+    let contests = contest_ids.into_iter().map(Contest::from_id).collect::<Vec<_>>();
+
+    let contest_from = fields.u32("contest_from")?;
+    let contest_to = fields.u32("contest_to")?;
+    if contest_from
+        .zip(contest_to)
+        .is_some_and(|(from, to)| from > to)
+    {
+        return Err(fields.error(
+            ErrorCode::MinGreaterThanMax,
+            "contest_from",
+            Message::GreaterThan(fields.path("contest_to")),
+        ));
+    }
+
+    let ranges = fields.array("difficulty")?;
+    if ranges.len() > MAX_DIFFICULTY_RANGES {
+        return Err(fields.error(
+            ErrorCode::TooManyValues,
+            "difficulty",
+            Message::TooManyRanges(MAX_DIFFICULTY_RANGES),
+        ));
+    }
+    let mut queries = Vec::with_capacity(ranges.len().max(1));
+    for (index, range) in ranges.iter().enumerate() {
+        let range = Fields::new(
+            range,
+            format!("{}[{}]", fields.path("difficulty"), index),
+            &["min", "max"],
+        )?;
+        let min = range.f64("min")?;
+        let max = range.f64("max")?;
+
+        if min.is_some_and(|min| min < MIN_DIFFICULTY) {
+            return Err(range.error(ErrorCode::OutOfRange, "min", Message::LessThanZero));
+        }
+        if min.zip(max).is_some_and(|(min, max)| min > max) {
+            return Err(range.error(
+                ErrorCode::MinGreaterThanMax,
+                "min",
+                Message::GreaterThan(range.path("max")),
+            ));
+        }
+
+        let mut builder = ProblemQuery::builder()
+            .contests(contests.clone())
+            .round_range(contest_from, contest_to);
+        if let Some(min) = min {
+            builder = builder.min(min);
+        }
+        if let Some(max) = max {
+            builder = builder.max(max);
+        }
+        queries.push(builder.build()?);
+    }
+    if queries.is_empty() {
+        queries.push(
+            ProblemQuery::builder()
+                .contests(contests)
+                .round_range(contest_from, contest_to)
+                .build()?,
+        );
+    }
+
+    let (include, exclude) = match fields.get("rules") {
+        None => (Vec::new(), Vec::new()),
+        Some(_) if depth >= MAX_RULE_DEPTH => {
+            return Err(fields.error(
+                ErrorCode::TooManyValues,
+                "rules",
+                Message::TooDeep(MAX_RULE_DEPTH),
+            ))
+        }
+        Some(value) => {
+            let rules = Fields::new(value, fields.path("rules"), &["include", "exclude"])?;
+            (
+                parse_rules(&rules, "include", depth + 1)?,
+                parse_rules(&rules, "exclude", depth + 1)?,
+            )
+        }
+    };
+
+    Ok(PickRule {
+        queries,
+        problem_ids: fields.problem_ids("problems", ErrorCode::InvalidProblemId)?,
+        include,
+        exclude,
+    })
+}
+
+fn parse_rules(fields: &Fields, key: &str, depth: usize) -> Result<Vec<PickRule>, ApiError> {
+    fields
+        .array(key)?
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let rule = Fields::new(rule, format!("{}[{}]", fields.path(key), index), &RULE_FIELDS)?;
+            parse_rule(&rule, depth)
+        })
+        .collect()
+}
@@ -0,0 +1,446 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::utils::api::{predict_solve_probability, Problem, ProblemModel};
+use crate::utils::routing::AppState;
+
+pub const MIN_DIFFICULTY: f64 = 0.0;
+
+/// コンテストの種類による絞り込み
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Contest {
+    ABC,
+    ARC,
+    AGC,
+    /// ABC/ARC/AGC 以外
+    Other,
+    /// コンテスト ID の接頭辞
+    Prefix(String),
+}
+
+impl Contest {
+    /// `contest` パラメータの値 (abc, arc, agc, other など) から作る
+    pub fn from_id(id: &str) -> Self {
+        if id.starts_with("abc") {
+            Contest::ABC
+        } else if id.starts_with("arc") {
+            Contest::ARC
+        } else if id.starts_with("agc") {
+            Contest::AGC
+        } else if id == "other" || id == "others" {
+            Contest::Other
+        } else {
+            Contest::Prefix(id.to_string())
+        }
+    }
+
+    /// 正規化済みのコンテスト ID がこの種類に当てはまるか
+    pub fn matches(&self, contest_id: &str) -> bool {
+        match self {
+            Contest::ABC => contest_id.starts_with("abc"),
+            Contest::ARC => contest_id.starts_with("arc"),
+            Contest::AGC => contest_id.starts_with("agc"),
+            Contest::Other => {
+                !contest_id.starts_with("abc")
+                    && !contest_id.starts_with("arc")
+                    && !contest_id.starts_with("agc")
+            }
+            Contest::Prefix(prefix) => contest_id.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// 難易度が不明な問題の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownDifficulty {
+    Include,
+    Exclude,
+}
+
+/// レーティング rating のユーザーが解ける確率で候補を絞り込む
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolveProbabilityRange {
+    pub rating: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// 絞り込み条件の誤り
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryError {
+    /// min が max より大きい
+    MinGreaterThanMax,
+    /// min が 0 未満
+    NegativeMin,
+    /// ABC/ARC/AGC の回の範囲が逆になっている
+    RoundRangeReversed,
+    /// レーティングが 0 未満
+    NegativeRating,
+    /// 解ける確率の下限が 0 以上 1 以下でない
+    ProbabilityMinOutOfRange,
+    /// 解ける確率の上限が 0 以上 1 以下でない
+    ProbabilityMaxOutOfRange,
+    /// 解ける確率の下限が上限より大きい
+    ProbabilityRangeReversed,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            QueryError::MinGreaterThanMax => "'min' cannot be greater than 'max'.",
+            QueryError::NegativeMin => "'min' cannot be less than 0.",
+            QueryError::RoundRangeReversed => "'contest_from' cannot be greater than 'contest_to'.",
+            QueryError::NegativeRating => "'rating' cannot be less than 0.",
+            QueryError::ProbabilityMinOutOfRange | QueryError::ProbabilityMaxOutOfRange => {
+                "'p_min' and 'p_max' must be between 0 and 1."
+            }
+            QueryError::ProbabilityRangeReversed => "'p_min' cannot be greater than 'p_max'.",
+        };
+        f.write_str(message)
+    }
+}
+
+impl Error for QueryError {}
+
+/// 問題を絞り込む条件。`ProblemQuery::builder()` で組み立てる
+#[derive(Debug, Clone)]
+pub struct ProblemQuery {
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+    pub(crate) allows_unknown_difficulty: bool,
+    pub(crate) contests: Vec<Contest>,
+    pub(crate) contest_from: Option<u32>,
+    pub(crate) contest_to: Option<u32>,
+    pub(crate) excluded_problem_ids: HashSet<String>,
+    pub(crate) solved_problem_ids: Arc<HashSet<String>>,
+    pub(crate) allows_experimental: bool,
+    pub(crate) solve_probability: Option<SolveProbabilityRange>,
+}
+
+impl Default for ProblemQuery {
+    fn default() -> Self {
+        Self {
+            min: MIN_DIFFICULTY,
+            max: f64::INFINITY,
+            allows_unknown_difficulty: true,
+            contests: Vec::new(),
+            contest_from: None,
+            contest_to: None,
+            excluded_problem_ids: HashSet::new(),
+            solved_problem_ids: Arc::default(),
+            allows_experimental: true,
+            solve_probability: None,
+        }
+    }
+}
+
+impl ProblemQuery {
+    pub fn builder() -> ProblemQueryBuilder {
+        ProblemQueryBuilder::default()
+    }
+
+    /// この条件を引き継いだビルダーを返す。一部の条件だけ差し替えるときに使う
+    pub fn to_builder(&self) -> ProblemQueryBuilder {
+        let unknown_difficulty = if self.allows_unknown_difficulty {
+            UnknownDifficulty::Include
+        } else {
+            UnknownDifficulty::Exclude
+        };
+
+        ProblemQueryBuilder {
+            min: Some(self.min),
+            max: Some(self.max),
+            unknown_difficulty: Some(unknown_difficulty),
+            contests: self.contests.clone(),
+            contest_from: self.contest_from,
+            contest_to: self.contest_to,
+            excluded_problem_ids: self.excluded_problem_ids.clone(),
+            solved_problem_ids: self.solved_problem_ids.clone(),
+            allows_experimental: Some(self.allows_experimental),
+            solve_probability: self.solve_probability,
+        }
+    }
+
+    /// 除外リストと解いた問題を除く条件をすべて満たすか
+    pub fn matches_conditions(&self, problem: &Problem, model: &ProblemModel) -> bool {
+        let contest_id = canonical_contest_id(&problem.id, &problem.contest_id);
+        if !self.contests.is_empty() && !self.contests.iter().any(|contest| contest.matches(contest_id)) {
+            return false;
+        }
+
+        let matches_round = match standard_contest_number(&problem.id) {
+            Some(number) => {
+                self.contest_from.is_none_or(|from| number >= from)
+                    && self.contest_to.is_none_or(|to| number <= to)
+            }
+            None => self.contest_from.is_none() && self.contest_to.is_none(),
+        };
+        if !matches_round {
+            return false;
+        }
+
+        let matches_difficulty = match model.difficulty {
+            Some(difficulty) => self.min <= difficulty && difficulty <= self.max,
+            None => self.allows_unknown_difficulty,
+        };
+        if !matches_difficulty || (!self.allows_experimental && model.is_experimental == Some(true)) {
+            return false;
+        }
+
+        self.solve_probability.is_none() || self.solve_probability(model).is_some()
+    }
+
+    pub fn is_excluded(&self, problem_id: &str) -> bool {
+        self.excluded_problem_ids.contains(problem_id) || self.solved_problem_ids.contains(problem_id)
+    }
+
+    /// 除外を含め、すべての条件を満たすか
+    pub fn matches(&self, problem: &Problem, model: &ProblemModel) -> bool {
+        !self.is_excluded(&problem.id) && self.matches_conditions(problem, model)
+    }
+
+    /// 条件を満たす問題をデータの順に返す。難易度モデルがない問題は含めない
+    pub fn candidates<'a>(&self, state: &'a AppState) -> Vec<&'a Problem> {
+        state
+            .problems
+            .iter()
+            .filter(|problem| {
+                state
+                    .problem_models
+                    .get(&problem.id)
+                    .is_some_and(|model| self.matches(problem, model))
+            })
+            .collect()
+    }
+
+    /// 解ける確率の条件があり、その範囲に入る場合に確率を返す
+    pub fn solve_probability(&self, model: &ProblemModel) -> Option<f64> {
+        let range = self.solve_probability?;
+        let probability = predict_solve_probability(model, range.rating)?;
+
+        (range.min <= probability && probability <= range.max).then_some(probability)
+    }
+
+    /// 除外リスト以外の条件を正規化した文字列。同じ条件なら書き方によらず同じになる
+    pub fn filter_key(&self) -> String {
+        // -0 と 0 を同じにする
+        let number = |value: f64| (value + 0.0).to_string();
+        let optional = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+
+        let mut contests = self
+            .contests
+            .iter()
+            .map(|contest| match contest {
+                Contest::ABC => "abc".to_string(),
+                Contest::ARC => "arc".to_string(),
+                Contest::AGC => "agc".to_string(),
+                Contest::Other => "other".to_string(),
+                Contest::Prefix(prefix) => prefix.clone(),
+            })
+            .collect::<Vec<_>>();
+        contests.sort();
+        contests.dedup();
+
+        let mut key = format!(
+            "min={}&max={}&unknown={}&contest={}&contest_from={}&contest_to={}&experimental={}",
+            number(self.min),
+            number(self.max),
+            self.allows_unknown_difficulty,
+            contests.join(","),
+            optional(self.contest_from),
+            optional(self.contest_to),
+            self.allows_experimental,
+        );
+        if let Some(range) = &self.solve_probability {
+            key.push_str(&format!(
+                "&rating={}&p_min={}&p_max={}",
+                number(range.rating),
+                number(range.min),
+                number(range.max)
+            ));
+        }
+
+        key
+    }
+
+    /// 問題を除外リストに加える
+    pub fn exclude<I, S>(&mut self, problem_ids: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.excluded_problem_ids
+            .extend(problem_ids.into_iter().map(Into::into));
+    }
+
+    /// ユーザーが解いた問題を除外する
+    pub fn exclude_solved(&mut self, solved_problem_ids: Arc<HashSet<String>>) {
+        self.solved_problem_ids = solved_problem_ids;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProblemQueryBuilder {
+    min: Option<f64>,
+    max: Option<f64>,
+    unknown_difficulty: Option<UnknownDifficulty>,
+    contests: Vec<Contest>,
+    contest_from: Option<u32>,
+    contest_to: Option<u32>,
+    excluded_problem_ids: HashSet<String>,
+    solved_problem_ids: Arc<HashSet<String>>,
+    allows_experimental: Option<bool>,
+    solve_probability: Option<SolveProbabilityRange>,
+}
+
+impl ProblemQueryBuilder {
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// 指定しない場合、min と max のどちらも指定していなければ含める
+    pub fn unknown_difficulty(mut self, policy: UnknownDifficulty) -> Self {
+        self.unknown_difficulty = Some(policy);
+        self
+    }
+
+    pub fn contest(mut self, contest: Contest) -> Self {
+        self.contests.push(contest);
+        self
+    }
+
+    pub fn contests<I: IntoIterator<Item = Contest>>(mut self, contests: I) -> Self {
+        self.contests.extend(contests);
+        self
+    }
+
+    /// ABC/ARC/AGC の回の範囲。指定すると、それ以外のコンテストの問題は含めない
+    pub fn round_range(mut self, from: Option<u32>, to: Option<u32>) -> Self {
+        self.contest_from = from;
+        self.contest_to = to;
+        self
+    }
+
+    pub fn exclude<I, S>(mut self, problem_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.excluded_problem_ids
+            .extend(problem_ids.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn exclude_solved(mut self, solved_problem_ids: Arc<HashSet<String>>) -> Self {
+        self.solved_problem_ids = solved_problem_ids;
+        self
+    }
+
+    /// 試験的な難易度の問題を含めるか (既定では含める)
+    pub fn experimental(mut self, allows_experimental: bool) -> Self {
+        self.allows_experimental = Some(allows_experimental);
+        self
+    }
+
+    pub fn solve_probability(mut self, rating: f64, min: f64, max: f64) -> Self {
+        self.solve_probability = Some(SolveProbabilityRange { rating, min, max });
+        self
+    }
+
+    pub fn build(self) -> Result<ProblemQuery, QueryError> {
+        let min = self.min.unwrap_or(MIN_DIFFICULTY);
+        let max = self.max.unwrap_or(f64::INFINITY);
+
+        if min > max {
+            return Err(QueryError::MinGreaterThanMax);
+        }
+
+        if min < MIN_DIFFICULTY {
+            return Err(QueryError::NegativeMin);
+        }
+
+        if self
+            .contest_from
+            .zip(self.contest_to)
+            .is_some_and(|(from, to)| from > to)
+        {
+            return Err(QueryError::RoundRangeReversed);
+        }
+
+        if let Some(range) = &self.solve_probability {
+            if range.rating < 0.0 {
+                return Err(QueryError::NegativeRating);
+            }
+
+            if !(0.0..=1.0).contains(&range.min) {
+                return Err(QueryError::ProbabilityMinOutOfRange);
+            }
+            if !(0.0..=1.0).contains(&range.max) {
+                return Err(QueryError::ProbabilityMaxOutOfRange);
+            }
+
+            if range.min > range.max {
+                return Err(QueryError::ProbabilityRangeReversed);
+            }
+        }
+
+        let allows_unknown_difficulty = match self.unknown_difficulty {
+            Some(policy) => policy == UnknownDifficulty::Include,
+            None => self.min.is_none() && self.max.is_none(),
+        };
+
+        Ok(ProblemQuery {
+            min,
+            max,
+            allows_unknown_difficulty,
+            contests: self.contests,
+            contest_from: self.contest_from,
+            contest_to: self.contest_to,
+            excluded_problem_ids: self.excluded_problem_ids,
+            solved_problem_ids: self.solved_problem_ids,
+            allows_experimental: self.allows_experimental.unwrap_or(true),
+            solve_probability: self.solve_probability,
+        })
+    }
+}
+
+fn standard_contest_id(problem_id: &str) -> Option<&str> {
+    let (contest_id, _) = problem_id.rsplit_once('_')?;
+
+    for prefix in ["abc", "arc", "agc"] {
+        if let Some(number) = contest_id.strip_prefix(prefix) {
+            if number.parse::<u32>().is_ok() {
+                return Some(contest_id);
+            }
+        }
+    }
+
+    None
+}
+
+/// 問題 ID が ABC/ARC/AGC のものならそのコンテスト ID を、そうでなければ contest_id を返す
+pub fn canonical_contest_id<'a>(problem_id: &'a str, contest_id: &'a str) -> &'a str {
+    standard_contest_id(problem_id).unwrap_or(contest_id)
+}
+
+/// ABC/ARC/AGC の問題なら何回目のコンテストかを返す
+pub fn standard_contest_number(problem_id: &str) -> Option<u32> {
+    let contest_id = standard_contest_id(problem_id)?;
+
+    for prefix in ["abc", "arc", "agc"] {
+        if let Some(number) = contest_id.strip_prefix(prefix) {
+            return number.parse().ok();
+        }
+    }
+
+    None
+}
@@ -0,0 +1,628 @@
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::utils::api::{Problem, ProblemModel};
+use crate::utils::error::{ApiError, ErrorCode};
+use crate::utils::i18n::Message;
+use crate::utils::problem_query::{
+    canonical_contest_id, standard_contest_number, Contest, ProblemQuery, UnknownDifficulty,
+};
+use crate::utils::routing::AppState;
+
+const MAX_EXCLUDED_PROBLEMS: usize = 20;
+const MAX_CONTESTS: usize = 10;
+const MAX_CONTEST_ID_LENGTH: usize = 32;
+pub(crate) const OPTIONAL_FIELDS: [&str; 1] = ["model"];
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+const DEFAULT_BUCKET_WIDTH: u32 = 400;
+const MAX_BUCKET_WIDTH: u32 = 4000;
+
+#[derive(Clone, Serialize)]
+pub(crate) struct ProblemResponse {
+    pub(crate) id: String,
+    pub(crate) contest_id: String,
+    pub(crate) name: String,
+    pub(crate) difficulty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) solve_probability: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) model: Option<ModelResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) url: Option<String>,
+}
+
+impl ProblemResponse {
+    pub(crate) fn new(problem: &Problem, model: Option<&ProblemModel>) -> Self {
+        Self {
+            id: problem.id.clone(),
+            contest_id: canonical_contest_id(&problem.id, &problem.contest_id).to_string(),
+            name: problem.name.clone(),
+            difficulty: model.and_then(|model| model.difficulty),
+            seed: None,
+            solve_probability: None,
+            model: None,
+            url: None,
+        }
+    }
+}
+
+/// `fields=model` を指定したときに返す難易度モデルの詳細
+#[derive(Clone, Serialize)]
+pub(crate) struct ModelResponse {
+    pub(crate) slope: Option<f64>,
+    pub(crate) intercept: Option<f64>,
+    pub(crate) variance: Option<f64>,
+    pub(crate) discrimination: Option<f64>,
+    pub(crate) is_experimental: Option<bool>,
+    pub(crate) irt_loglikelihood: Option<f64>,
+}
+
+impl From<&ProblemModel> for ModelResponse {
+    fn from(model: &ProblemModel) -> Self {
+        Self {
+            slope: model.slope,
+            intercept: model.intercept,
+            variance: model.variance,
+            discrimination: model.discrimination,
+            is_experimental: model.is_experimental,
+            irt_loglikelihood: model.irt_loglikelihood,
+        }
+    }
+}
+
+pub(crate) fn parse_optional_f64(params: &HashMap<String, String>, key: &str) -> Result<Option<f64>, ApiError> {
+    params
+        .get(key)
+        .map(|value| {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Some)
+                .ok_or_else(|| {
+                    ApiError::invalid(ErrorCode::InvalidNumber, key, Message::NotANumber)
+                })
+        })
+        .transpose()
+        .map(Option::flatten)
+}
+
+pub(crate) fn parse_optional_u32(params: &HashMap<String, String>, key: &str) -> Result<Option<u32>, ApiError> {
+    params
+        .get(key)
+        .map(|value| {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+
+            value
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| {
+                    ApiError::invalid(
+                        ErrorCode::InvalidInteger,
+                        key,
+                        Message::NotAPositiveInteger,
+                    )
+                })
+        })
+        .transpose()
+        .map(Option::flatten)
+}
+
+pub(crate) fn parse_optional_u64(params: &HashMap<String, String>, key: &str) -> Result<Option<u64>, ApiError> {
+    params
+        .get(key)
+        .map(|value| {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+
+            value
+                .parse::<u64>()
+                .map(Some)
+                .map_err(|_| {
+                    ApiError::invalid(
+                        ErrorCode::InvalidInteger,
+                        key,
+                        Message::NotANonNegativeInteger,
+                    )
+                })
+        })
+        .transpose()
+        .map(Option::flatten)
+}
+
+pub(crate) fn parse_optional_bool(params: &HashMap<String, String>, key: &str) -> Result<Option<bool>, ApiError> {
+    params
+        .get(key)
+        .map(|value| match value.trim() {
+            "" => Ok(None),
+            "true" => Ok(Some(true)),
+            "false" => Ok(Some(false)),
+            _ => Err(ApiError::invalid(
+                ErrorCode::InvalidBoolean,
+                key,
+                Message::NotABoolean,
+            )),
+        })
+        .transpose()
+        .map(Option::flatten)
+}
+
+pub(crate) fn parse_fields(params: &HashMap<String, String>) -> Result<Vec<&'static str>, ApiError> {
+    params
+        .get("fields")
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .map(|field| {
+                    OPTIONAL_FIELDS
+                        .into_iter()
+                        .find(|&known| known == field)
+                        .ok_or_else(|| {
+                            ApiError::invalid(
+                                ErrorCode::InvalidValue,
+                                "fields",
+                                Message::UnknownOptionalField(field.to_string()),
+                            )
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| Ok(Vec::new()))
+}
+
+/// `rating` と解ける確率の範囲 (`p_min`, `p_max`) を読む。範囲の検証は `ProblemQuery` で行う
+pub(crate) fn parse_solve_probability_range(
+    params: &HashMap<String, String>,
+) -> Result<Option<(f64, f64, f64)>, ApiError> {
+    let rating = parse_optional_f64(params, "rating")?;
+    let min = parse_optional_f64(params, "p_min")?;
+    let max = parse_optional_f64(params, "p_max")?;
+
+    let Some(rating) = rating else {
+        if min.is_some() || max.is_some() {
+            return Err(ApiError::invalid(
+                ErrorCode::MissingParameter,
+                "rating",
+                Message::RatingRequired,
+            ));
+        }
+        return Ok(None);
+    };
+
+    Ok(Some((rating, min.unwrap_or(0.0), max.unwrap_or(1.0))))
+}
+
+pub(crate) fn parse_excluded_problem_ids(params: &HashMap<String, String>) -> Result<HashSet<String>, ApiError> {
+    let excluded = params
+        .get("exclude")
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(ToString::to_string)
+                .collect::<HashSet<String>>()
+        })
+        .unwrap_or_default();
+
+    if excluded.len() > MAX_EXCLUDED_PROBLEMS {
+        return Err(ApiError::invalid(
+            ErrorCode::TooManyValues,
+            "exclude",
+            Message::TooManyProblemIds(MAX_EXCLUDED_PROBLEMS),
+        ));
+    }
+
+    if !excluded.iter().all(|id| is_valid_problem_id(id)) {
+        return Err(ApiError::invalid(
+            ErrorCode::InvalidExcludeId,
+            "exclude",
+            Message::InvalidExcludeId,
+        ));
+    }
+
+    Ok(excluded)
+}
+
+pub(crate) fn is_valid_problem_id(id: &str) -> bool {
+    id.len() <= 100
+        && id
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '-'))
+}
+
+pub(crate) fn task_url(contest_id: &str, problem_id: &str) -> String {
+    format!(
+        "https://atcoder.jp/contests/{}/tasks/{}",
+        contest_id, problem_id
+    )
+}
+
+/// コンテストの指定が多すぎたり長すぎたりしないか確かめる
+pub(crate) fn check_contests(param: &str, contest_ids: &[&str]) -> Result<(), ApiError> {
+    if contest_ids.len() > MAX_CONTESTS {
+        return Err(ApiError::invalid(
+            ErrorCode::TooManyValues,
+            param,
+            Message::TooManyContests(MAX_CONTESTS),
+        ));
+    }
+
+    if contest_ids.iter().any(|id| id.len() > MAX_CONTEST_ID_LENGTH) {
+        return Err(ApiError::invalid(
+            ErrorCode::InvalidValue,
+            param,
+            Message::ContestTooLong(MAX_CONTEST_ID_LENGTH),
+        ));
+    }
+
+    Ok(())
+}
+
+/// クエリパラメータから読んだ絞り込み条件と、レスポンスに含める項目
+pub(crate) struct ProblemFilter {
+    pub(crate) query: ProblemQuery,
+    pub(crate) includes_model: bool,
+}
+
+pub(crate) fn parse_problem_filter(params: &HashMap<String, String>) -> Result<ProblemFilter, ApiError> {
+    let mut builder = ProblemQuery::builder();
+    if let Some(min) = parse_optional_f64(params, "min")? {
+        builder = builder.min(min);
+    }
+    if let Some(max) = parse_optional_f64(params, "max")? {
+        builder = builder.max(max);
+    }
+    // min/max のどちらかを指定した場合は、空の値でも難易度不明の問題を除く
+    builder = builder.unknown_difficulty(
+        if params.contains_key("min") || params.contains_key("max") {
+            UnknownDifficulty::Exclude
+        } else {
+            UnknownDifficulty::Include
+        },
+    );
+
+    let contest_ids = params
+        .get("contest")
+        .map(|s| {
+            s.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    check_contests("contest", &contest_ids)?;
+    let contests = contest_ids.into_iter().map(Contest::from_id).collect::<Vec<_>>();
+
+    let contest_from = parse_optional_u32(params, "contest_from")?;
+    let contest_to = parse_optional_u32(params, "contest_to")?;
+    let excluded_problem_ids = parse_excluded_problem_ids(params)?;
+    let allows_experimental = parse_optional_bool(params, "experimental")?.unwrap_or(true);
+    let includes_model = parse_fields(params)?.contains(&"model");
+    if let Some((rating, min, max)) = parse_solve_probability_range(params)? {
+        builder = builder.solve_probability(rating, min, max);
+    }
+
+    let query = builder
+        .contests(contests)
+        .round_range(contest_from, contest_to)
+        .exclude(excluded_problem_ids)
+        .experimental(allows_experimental)
+        .build()?;
+
+    Ok(ProblemFilter {
+        query,
+        includes_model,
+    })
+}
+
+/// 除外リストを適用する前の候補問題を返す
+pub(crate) fn collect_candidates(state: &AppState, filter: &ProblemFilter) -> Vec<ProblemResponse> {
+    collect_candidates_where(state, filter, |_, _| true)
+}
+
+/// `collect_candidates` に加えて、`rule` を満たす問題だけを返す
+pub(crate) fn collect_candidates_where(
+    state: &AppState,
+    filter: &ProblemFilter,
+    rule: impl Fn(&Problem, &ProblemModel) -> bool,
+) -> Vec<ProblemResponse> {
+    state
+        .problems
+        .iter()
+        .filter_map(|p| {
+            let m = state.problem_models.get(&p.id)?;
+            if !filter.query.matches_conditions(p, m) || !rule(p, m) {
+                return None;
+            }
+
+            Some(ProblemResponse {
+                solve_probability: filter.query.solve_probability(m),
+                model: filter.includes_model.then(|| ModelResponse::from(m)),
+                ..ProblemResponse::new(p, Some(m))
+            })
+        })
+        .collect::<Vec<ProblemResponse>>()
+}
+
+/// 除外リストと解いた問題を候補から取り除く
+pub(crate) fn apply_exclusions(
+    candidates: Vec<ProblemResponse>,
+    filter: &ProblemFilter,
+) -> Vec<ProblemResponse> {
+    candidates
+        .into_iter()
+        .filter(|problem| !filter.query.is_excluded(&problem.id))
+        .collect()
+}
+
+/// 除外リストを適用した候補問題を返す。候補がない場合はその理由を返す
+pub(crate) fn select_candidates(
+    state: &AppState,
+    filter: &ProblemFilter,
+) -> Result<Vec<ProblemResponse>, ApiError> {
+    select_candidates_where(state, filter, |_, _| true)
+}
+
+pub(crate) fn select_candidates_where(
+    state: &AppState,
+    filter: &ProblemFilter,
+    rule: impl Fn(&Problem, &ProblemModel) -> bool,
+) -> Result<Vec<ProblemResponse>, ApiError> {
+    let candidates = collect_candidates_where(state, filter, rule);
+    let had_candidates_before_exclusion = !candidates.is_empty();
+    let candidates = apply_exclusions(candidates, filter);
+
+    if candidates.is_empty() {
+        let query = &filter.query;
+        // 配布データによっては識別力がなく、解ける確率を計算できない
+        if query.solve_probability.is_some()
+            && !state
+                .problem_models
+                .values()
+                .any(|model| model.discrimination.is_some())
+        {
+            return Err(ApiError::invalid(
+                ErrorCode::ModelUnavailable,
+                "rating",
+                Message::SolveProbabilityUnavailable,
+            ));
+        }
+
+        let (code, message) = if had_candidates_before_exclusion && !query.excluded_problem_ids.is_empty()
+        {
+            (ErrorCode::ExcludedAll, Message::ExcludedAll)
+        } else if had_candidates_before_exclusion && !query.solved_problem_ids.is_empty() {
+            (ErrorCode::SolvedAll, Message::SolvedAll)
+        } else {
+            (ErrorCode::NoCandidates, Message::NoCandidates)
+        };
+        return Err(ApiError::new(code, message));
+    }
+
+    Ok(candidates)
+}
+
+/// 一覧の並び順。`-` を付けると降順になる
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SortKey {
+    Difficulty,
+    Contest,
+    Id,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ProblemSort {
+    pub(crate) key: SortKey,
+    pub(crate) descending: bool,
+}
+
+impl ProblemSort {
+    /// 難易度不明の問題は昇順・降順どちらでも末尾に置き、同順位は ID で並べる
+    pub(crate) fn compare(&self, a: &ProblemResponse, b: &ProblemResponse) -> Ordering {
+        let ordering = match self.key {
+            SortKey::Difficulty => match (a.difficulty, b.difficulty) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            SortKey::Contest => contest_order(a).cmp(&contest_order(b)),
+            SortKey::Id => Ordering::Equal,
+        };
+        let ordering = ordering.then_with(|| a.id.cmp(&b.id));
+
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+/// ABC/ARC/AGC はコンテスト番号順、それ以外はコンテスト ID 順に並べる
+fn contest_order(problem: &ProblemResponse) -> (String, Option<u32>) {
+    match standard_contest_number(&problem.id) {
+        Some(number) => (problem.contest_id[..3].to_string(), Some(number)),
+        None => (problem.contest_id.clone(), None),
+    }
+}
+
+pub(crate) fn parse_sort(params: &HashMap<String, String>) -> Result<ProblemSort, ApiError> {
+    let value = params.get("sort").map(|value| value.trim()).unwrap_or("");
+    let (descending, name) = match value.strip_prefix('-') {
+        Some(name) => (true, name),
+        None => (false, value),
+    };
+
+    let key = match name {
+        "" | "id" => SortKey::Id,
+        "difficulty" => SortKey::Difficulty,
+        "contest" => SortKey::Contest,
+        _ => {
+            return Err(ApiError::invalid(
+                ErrorCode::InvalidValue,
+                "sort",
+                Message::InvalidSort,
+            ))
+        }
+    };
+
+    Ok(ProblemSort { key, descending })
+}
+
+pub(crate) fn parse_page_size(params: &HashMap<String, String>) -> Result<usize, ApiError> {
+    match parse_optional_u32(params, "limit")? {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(limit) if limit == 0 || limit as usize > MAX_PAGE_SIZE => Err(ApiError::invalid(
+            ErrorCode::OutOfRange,
+            "limit",
+            Message::Between(1, MAX_PAGE_SIZE as u64),
+        )),
+        Some(limit) => Ok(limit as usize),
+    }
+}
+
+/// 一覧の 1 ページ分
+pub(crate) struct ProblemPage {
+    pub(crate) total: usize,
+    pub(crate) problems: Vec<ProblemResponse>,
+    pub(crate) next_cursor: Option<String>,
+}
+
+/// 並べ替えた候補から、`cursor` の問題より後ろを最大 `limit` 件返す。
+/// カーソルは直前のページの最後の問題 ID で、データが更新されても位置がずれない
+pub(crate) fn paginate(
+    state: &AppState,
+    mut problems: Vec<ProblemResponse>,
+    sort: ProblemSort,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<ProblemPage, ApiError> {
+    problems.sort_by(|a, b| sort.compare(a, b));
+    let total = problems.len();
+
+    let start = match cursor.map(str::trim).filter(|cursor| !cursor.is_empty()) {
+        None => 0,
+        Some(cursor) => {
+            let after = state
+                .problem(cursor)
+                .map(|problem| ProblemResponse::new(problem, state.problem_models.get(&problem.id)))
+                .ok_or_else(|| {
+                    ApiError::invalid(ErrorCode::InvalidValue, "cursor", Message::InvalidCursor)
+                })?;
+            problems.partition_point(|problem| sort.compare(problem, &after) != Ordering::Greater)
+        }
+    };
+
+    let problems = problems
+        .into_iter()
+        .skip(start)
+        .take(limit)
+        .collect::<Vec<_>>();
+    let next_cursor = (start + problems.len() < total)
+        .then(|| problems.last().map(|problem| problem.id.clone()))
+        .flatten();
+
+    Ok(ProblemPage {
+        total,
+        problems,
+        next_cursor,
+    })
+}
+
+pub(crate) fn parse_bucket_width(params: &HashMap<String, String>) -> Result<u32, ApiError> {
+    match parse_optional_u32(params, "bucket_width")? {
+        None => Ok(DEFAULT_BUCKET_WIDTH),
+        Some(width) if width == 0 || width > MAX_BUCKET_WIDTH => Err(ApiError::invalid(
+            ErrorCode::OutOfRange,
+            "bucket_width",
+            Message::Between(1, MAX_BUCKET_WIDTH as u64),
+        )),
+        Some(width) => Ok(width),
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct DifficultyBucket {
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+    pub(crate) count: usize,
+}
+
+#[derive(Default, Serialize)]
+pub(crate) struct ContestTypeCounts {
+    pub(crate) abc: usize,
+    pub(crate) arc: usize,
+    pub(crate) agc: usize,
+    pub(crate) other: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ProblemStats {
+    pub(crate) count: usize,
+    pub(crate) unknown_difficulty: usize,
+    pub(crate) bucket_width: u32,
+    /// 難易度の最小の帯から最大の帯まで、問題がない帯も含めて並べる
+    pub(crate) histogram: Vec<DifficultyBucket>,
+    pub(crate) contests: ContestTypeCounts,
+}
+
+pub(crate) fn problem_stats(problems: &[ProblemResponse], bucket_width: u32) -> ProblemStats {
+    let width = f64::from(bucket_width);
+    let mut buckets: HashMap<i64, usize> = HashMap::new();
+    let mut contests = ContestTypeCounts::default();
+    for problem in problems {
+        if let Some(difficulty) = problem.difficulty {
+            *buckets
+                .entry((difficulty / width).floor() as i64)
+                .or_default() += 1;
+        }
+
+        let counter = match problem.contest_id.get(..3) {
+            Some("abc") => &mut contests.abc,
+            Some("arc") => &mut contests.arc,
+            Some("agc") => &mut contests.agc,
+            _ => &mut contests.other,
+        };
+        *counter += 1;
+    }
+
+    let histogram = match (buckets.keys().min(), buckets.keys().max()) {
+        (Some(&lowest), Some(&highest)) => (lowest..=highest)
+            .map(|index| DifficultyBucket {
+                min: index as f64 * width,
+                max: (index + 1) as f64 * width,
+                count: buckets.get(&index).copied().unwrap_or(0),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    ProblemStats {
+        count: problems.len(),
+        unknown_difficulty: problems
+            .iter()
+            .filter(|problem| problem.difficulty.is_none())
+            .count(),
+        bucket_width,
+        histogram,
+        contests,
+    }
+}
@@ -1,37 +1,295 @@
+use hyper::header::{self, HeaderMap, HeaderName, HeaderValue};
+use hyper::{Body, Response};
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
+use std::hash::BuildHasher;
 use std::time::{Duration, Instant};
 use std::net::IpAddr;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::utils::client_ip::rate_limit_key;
+use crate::utils::error::{ApiError, ErrorCode};
+use crate::utils::i18n::{Lang, Message};
+
+const SHARD_COUNT: usize = 64;
+
+/// トークンバケットの容量と、1 秒あたりに補充するトークン数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub capacity: f64,
+    pub refill_per_sec: f64,
+}
+
+impl RateLimit {
+    /// 1 IP あたり 1 秒に 1 リクエスト
+    pub const ONE_PER_SECOND: RateLimit = RateLimit {
+        capacity: 1.0,
+        refill_per_sec: 1.0,
+    };
+
+    pub fn new(capacity: f64, refill_per_sec: f64) -> Result<Self, String> {
+        if !(capacity.is_finite() && capacity >= 1.0) {
+            return Err(format!("capacity must be at least 1: {}", capacity));
+        }
+        if !(refill_per_sec.is_finite() && refill_per_sec > 0.0) {
+            return Err(format!("refill rate must be positive: {}", refill_per_sec));
+        }
+        // 待ち時間は容量 / 補充数を超えないので、ここで Duration に収まるか確かめておく
+        if Duration::try_from_secs_f64(capacity / refill_per_sec).is_err() {
+            return Err(format!(
+                "capacity / refill rate is too long: {} / {}",
+                capacity, refill_per_sec
+            ));
+        }
+
+        Ok(Self {
+            capacity,
+            refill_per_sec,
+        })
+    }
+
+    /// `<容量>:<毎秒の補充数>` (例: `10:2`) か、プリセット名 `1rps` を読む
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if spec == "1rps" {
+            return Ok(Self::ONE_PER_SECOND);
+        }
+
+        let (capacity, refill) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("rate limit must be '<capacity>:<refill per second>': {}", spec))?;
+        let capacity = capacity
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid capacity: {}", capacity))?;
+        let refill = refill
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid refill rate: {}", refill))?;
+
+        Self::new(capacity, refill)
+    }
+
+    // 空のバケットが満たされるまでの時間。これ以上使われていないバケットは捨ててよい
+    fn time_to_fill(&self) -> Duration {
+        Duration::from_secs_f64(self.capacity / self.refill_per_sec)
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self::ONE_PER_SECOND
+    }
+}
+
+/// 全体の制限と、パスごとの制限
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitConfig {
+    pub default: RateLimit,
+    /// 先に書いたものを優先する。`/problems` は `/problems/abc001_a` にも一致する
+    pub routes: Vec<(String, RateLimit)>,
+}
+
+impl RateLimitConfig {
+    /// `RATE_LIMIT` と `RATE_LIMIT_ROUTES` (例: `/stats=30:5;/pick=5:1`) から読む
+    pub fn from_env() -> Result<Self, String> {
+        let default = match std::env::var("RATE_LIMIT") {
+            Ok(spec) if !spec.trim().is_empty() => {
+                RateLimit::parse(&spec).map_err(|e| format!("RATE_LIMIT: {}", e))?
+            }
+            _ => RateLimit::default(),
+        };
+        let routes = match std::env::var("RATE_LIMIT_ROUTES") {
+            Ok(routes) => Self::parse_routes(&routes).map_err(|e| format!("RATE_LIMIT_ROUTES: {}", e))?,
+            Err(_) => Vec::new(),
+        };
+
+        Ok(Self { default, routes })
+    }
+
+    pub fn parse_routes(routes: &str) -> Result<Vec<(String, RateLimit)>, String> {
+        routes
+            .split(';')
+            .map(str::trim)
+            .filter(|route| !route.is_empty())
+            .map(|route| {
+                let (path, spec) = route
+                    .split_once('=')
+                    .ok_or_else(|| format!("route must be '<path>=<rate limit>': {}", route))?;
+                let path = path.trim();
+                if !path.starts_with('/') {
+                    return Err(format!("path must start with '/': {}", path));
+                }
+                Ok((path.to_string(), RateLimit::parse(spec)?))
+            })
+            .collect()
+    }
+
+    // パスに対応する制限。バケットを分けるため、制限の番号も返す
+    fn limit_for(&self, path: &str) -> (usize, RateLimit) {
+        self.routes
+            .iter()
+            .position(|(route, _)| {
+                path == route
+                    || path
+                        .strip_prefix(route.trim_end_matches('/'))
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|index| (index + 1, self.routes[index].1))
+            .unwrap_or((0, self.default))
+    }
+
+    fn limit_at(&self, index: usize) -> RateLimit {
+        match index {
+            0 => self.default,
+            index => self.routes[index - 1].1,
+        }
+    }
+}
+
+/// 判定結果と、`RateLimit-*` ヘッダーに載せる残り回数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitStatus {
+    pub allowed: bool,
+    pub limit: u64,
+    pub remaining: u64,
+    /// バケットが満杯に戻るまでの時間
+    pub reset: Duration,
+    /// 拒否した場合に、次のトークンが貯まるまでの時間
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    /// `RateLimit-Limit` / `RateLimit-Remaining` / `RateLimit-Reset` と、拒否時は `Retry-After` を付ける
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(HeaderName::from_static("ratelimit-limit"), HeaderValue::from(self.limit));
+        headers.insert(
+            HeaderName::from_static("ratelimit-remaining"),
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert(
+            HeaderName::from_static("ratelimit-reset"),
+            HeaderValue::from(ceil_secs(self.reset)),
+        );
+        if let Some(retry_after) = self.retry_after {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(ceil_secs(retry_after).max(1)));
+        }
+    }
+
+    /// 拒否したリクエストに返す 429 のレスポンス
+    pub fn rejected_response(&self, lang: Lang) -> Response<Body> {
+        let mut res = ApiError::new(ErrorCode::RateLimited, Message::RateLimited).into_response(lang);
+        self.apply_headers(res.headers_mut());
+        res
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil() as u64
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+type Shard = Mutex<HashMap<(IpAddr, usize), Bucket>>;
 
 #[derive(Clone)]
 pub struct RateLimiter {
-    pub last_request: Arc<Mutex<HashMap<IpAddr, Instant>>>,
-    pub ttl: Duration,
+    // IP ごとにシャードを分け、リクエストが同じロックを奪い合わないようにする
+    shards: Arc<[Shard]>,
+    hasher: RandomState,
+    config: Arc<RateLimitConfig>,
 }
 
 impl RateLimiter {
-    pub fn new() -> Self {
-        Self { 
-            last_request: Arc::new(Mutex::new(HashMap::new())),
-            ttl: Duration::from_secs(600),
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            shards: (0..SHARD_COUNT).map(|_| Mutex::default()).collect(),
+            hasher: RandomState::new(),
+            config: Arc::new(config),
         }
     }
 
-    pub async fn check(&self, ip: IpAddr) -> bool {
-        let mut map = self.last_request.lock().await;
-        let now = Instant::now();
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
 
-        map.retain(|_, &mut last| now.duration_since(last) < self.ttl);
+    fn shard(&self, key: &(IpAddr, usize)) -> &Shard {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        &self.shards[index]
+    }
 
-        match map.get(&ip) {
-            Some(&last) if now.duration_since(last) < Duration::from_secs(1) => {
-                false
-            },
-            _ => {
-                map.insert(ip, now);
-                true
-            }
+    pub async fn check(&self, ip: IpAddr, path: &str) -> RateLimitStatus {
+        self.check_at(ip, path, Instant::now()).await
+    }
+
+    /// `now` の時点でトークンを 1 つ消費できれば通す。IPv6 は /64 ごとにバケットを共有する
+    pub async fn check_at(&self, ip: IpAddr, path: &str, now: Instant) -> RateLimitStatus {
+        let (index, limit) = self.config.limit_for(path);
+        let key = (rate_limit_key(ip), index);
+        let mut buckets = self.shard(&key).lock().unwrap();
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: limit.capacity,
+            updated_at: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * limit.refill_per_sec).min(limit.capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let refill_secs = |tokens: f64| Duration::from_secs_f64(tokens.max(0.0) / limit.refill_per_sec);
+
+        RateLimitStatus {
+            allowed,
+            limit: limit.capacity as u64,
+            remaining: bucket.tokens as u64,
+            reset: refill_secs(limit.capacity - bucket.tokens),
+            retry_after: (!allowed).then(|| refill_secs(1.0 - bucket.tokens)),
         }
     }
-}
\ No newline at end of file
+
+    /// 記録しているバケットの数
+    pub fn tracked(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+
+    /// 満杯まで回復したバケットは新しいものと同じなので捨てる。捨てた数を返す
+    pub fn evict_idle(&self, now: Instant) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let mut buckets = shard.lock().unwrap();
+                let before = buckets.len();
+                buckets.retain(|&(_, index), bucket| {
+                    let limit = self.config.limit_at(index);
+                    now.saturating_duration_since(bucket.updated_at) < limit.time_to_fill()
+                });
+                before - buckets.len()
+            })
+            .sum()
+    }
+
+    /// 使われなくなったバケットを定期的に捨てる
+    pub fn spawn_eviction(&self, interval: Duration) -> JoinHandle<()> {
+        let limiter = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                limiter.evict_idle(Instant::now());
+            }
+        })
+    }
+}
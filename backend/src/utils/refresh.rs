@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::utils::api::{self, Problem, ProblemModelRaw};
+use crate::utils::validation;
+
+pub const DEFAULT_SOURCE: &str = "https://kenkoooo.com/atcoder/resources";
+const USER_AGENT: &str = "atrp-updater";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+// サマリーに列挙する ID の上限
+const MAX_LISTED_IDS: usize = 20;
+
+/// `backend refresh-data` の引数
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshOptions {
+    pub source: String,
+    pub out: PathBuf,
+}
+
+impl RefreshOptions {
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut source = DEFAULT_SOURCE.to_string();
+        let mut out = api::data_dir();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("'{}' requires a value.", arg))
+            };
+
+            match arg.as_str() {
+                "--source" => source = value()?,
+                "--out" => out = PathBuf::from(value()?),
+                _ => return Err(format!("unknown argument '{}'.", arg)),
+            }
+        }
+
+        Ok(Self {
+            source: source.trim_end_matches('/').to_string(),
+            out,
+        })
+    }
+}
+
+/// 更新前後の差分
+#[derive(Debug, Clone, Default)]
+pub struct RefreshSummary {
+    pub previous_count: usize,
+    pub current_count: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// 上流にモデルがなく、難易度不明として書き出した問題の数
+    pub problems_without_models: usize,
+    /// 問題一覧にないため捨てたモデルの数
+    pub models_without_problems: usize,
+}
+
+impl RefreshSummary {
+    pub fn print(&self) {
+        println!(
+            "problems: {} -> {} (+{}, -{})",
+            self.previous_count,
+            self.current_count,
+            self.added.len(),
+            self.removed.len()
+        );
+        println!(
+            "models: {} problems without models, {} models without problems",
+            self.problems_without_models, self.models_without_problems
+        );
+        for (sign, ids) in [("+", &self.added), ("-", &self.removed)] {
+            for id in ids.iter().take(MAX_LISTED_IDS) {
+                println!("  {} {}", sign, id);
+            }
+            if ids.len() > MAX_LISTED_IDS {
+                println!("  {} ... and {} more", sign, ids.len() - MAX_LISTED_IDS);
+            }
+        }
+    }
+}
+
+async fn download<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    let res = client.get(url).send().await?.error_for_status()?;
+    Ok(serde_json::from_slice(&res.bytes().await?)?)
+}
+
+/// 置き換え用の一時ファイルを同じディレクトリに書き出す
+fn write_temp_file(path: &Path, contents: &[u8]) -> std::io::Result<PathBuf> {
+    let file_name = path.file_name().unwrap().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    fs::write(&temp_path, contents)?;
+    Ok(temp_path)
+}
+
+/// 上流の API から problems.json と problem-models.json を取得し、検証してから書き出す
+pub async fn refresh_data(options: &RefreshOptions) -> Result<RefreshSummary, Box<dyn Error + Send + Sync>> {
+    let client = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+
+    let problems: Vec<Problem> =
+        download(&client, &format!("{}/problems.json", options.source)).await?;
+    let mut raw_models: HashMap<String, ProblemModelRaw> =
+        download(&client, &format!("{}/problem-models.json", options.source)).await?;
+
+    if problems.is_empty() {
+        return Err(format!("{} returned no problems", options.source).into());
+    }
+
+    // 上流のモデルは新しい問題の分が遅れて追加されるので、モデルのない問題があるのは正常。
+    // 重複した ID と、モデルが 1 件も一致しない場合だけを取得の失敗とみなす
+    let report = validation::validate_problems(&problems, &raw_models);
+    let has_any_model = problems.iter().any(|problem| raw_models.contains_key(&problem.id));
+    if !report.duplicated_problem_ids.is_empty() || !has_any_model {
+        return Err(report.into());
+    }
+
+    // json_fetch.mjs と同様に、問題一覧にある問題のモデルだけを残す
+    let raw_models = problems
+        .iter()
+        .map(|problem| {
+            let raw = raw_models
+                .remove(&problem.id)
+                .unwrap_or_default();
+            (problem.id.clone(), raw)
+        })
+        .collect::<BTreeMap<String, ProblemModelRaw>>();
+
+    fs::create_dir_all(&options.out)?;
+    let (problems_path, problem_models_path) = api::data_file_paths(&options.out);
+
+    let previous_ids = fs::read_to_string(&problems_path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<Vec<Problem>>(&text).ok())
+        .map(|problems| {
+            problems
+                .into_iter()
+                .map(|problem| problem.id)
+                .collect::<HashSet<String>>()
+        })
+        .unwrap_or_default();
+    let current_ids = problems
+        .iter()
+        .map(|problem| problem.id.clone())
+        .collect::<HashSet<String>>();
+
+    let mut added = current_ids
+        .difference(&previous_ids)
+        .cloned()
+        .collect::<Vec<String>>();
+    added.sort();
+    let mut removed = previous_ids
+        .difference(&current_ids)
+        .cloned()
+        .collect::<Vec<String>>();
+    removed.sort();
+
+    let problems_temp = write_temp_file(&problems_path, &serde_json::to_vec(&problems)?)?;
+    let problem_models_temp =
+        write_temp_file(&problem_models_path, &serde_json::to_vec(&raw_models)?)?;
+    fs::rename(problems_temp, &problems_path)?;
+    fs::rename(problem_models_temp, &problem_models_path)?;
+
+    Ok(RefreshSummary {
+        previous_count: previous_ids.len(),
+        current_count: current_ids.len(),
+        added,
+        removed,
+        problems_without_models: report.problems_without_models.len(),
+        models_without_problems: report.models_without_problems.len(),
+    })
+}
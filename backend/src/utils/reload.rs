@@ -0,0 +1,211 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use crate::utils::api::{self, fnv1a, DataSource, FNV_OFFSET_BASIS};
+use crate::utils::routing::AppState;
+use crate::utils::validation::{self, ValidationReport};
+
+/// 配信中のデータがどのスナップショットかを表す情報
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub loaded_at: DateTime<Utc>,
+    pub problems_modified_at: Option<DateTime<Utc>>,
+    pub problem_models_modified_at: Option<DateTime<Utc>>,
+    pub problem_count: usize,
+    pub model_count: usize,
+    pub content_hash: Option<String>,
+}
+
+impl SnapshotInfo {
+    /// ファイルに由来しない状態の情報
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            loaded_at: Utc::now(),
+            problems_modified_at: None,
+            problem_models_modified_at: None,
+            problem_count: state.problems.len(),
+            model_count: state.problem_models.len(),
+            content_hash: None,
+        }
+    }
+}
+
+/// データディレクトリから AppState とそのスナップショット情報を読み込む。
+/// 厳密モードでは検証に失敗した場合に ValidationReport をエラーとして返す
+pub async fn load_snapshot(
+    source: &DataSource,
+) -> Result<(AppState, SnapshotInfo), Box<dyn Error + Send + Sync>> {
+    let dir = source.dir.as_path();
+    let missing_files = validation::missing_data_files(dir);
+    if source.strict && !missing_files.is_empty() {
+        return Err(ValidationReport {
+            missing_files,
+            ..ValidationReport::default()
+        }
+        .into());
+    }
+
+    let (problems_path, problem_models_path) = api::data_file_paths(dir);
+    let modified_at = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(DateTime::<Utc>::from)
+    };
+    let problems_modified_at = modified_at(&problems_path);
+    let problem_models_modified_at = modified_at(&problem_models_path);
+
+    let (problems, problem_models) = api::fetch_problem_from(dir).await?;
+
+    let report = ValidationReport {
+        missing_files,
+        ..validation::validate_problems(&problems, &problem_models)
+    };
+    if !report.is_valid() {
+        if source.strict {
+            return Err(report.into());
+        }
+        eprintln!("Warning: {}", report);
+    }
+
+    let content_hash = fs::read(&problems_path)
+        .and_then(|problems_bytes| Ok((problems_bytes, fs::read(&problem_models_path)?)))
+        .ok()
+        .map(|(problems_bytes, problem_models_bytes)| {
+            let hash = fnv1a(FNV_OFFSET_BASIS, &problems_bytes);
+            format!("{:016x}", fnv1a(hash, &problem_models_bytes))
+        });
+
+    let state = AppState::new(problems, problem_models);
+    let info = SnapshotInfo {
+        problems_modified_at,
+        problem_models_modified_at,
+        content_hash,
+        ..SnapshotInfo::from_state(&state)
+    };
+
+    Ok((state, info))
+}
+
+struct Snapshot {
+    state: Arc<AppState>,
+    info: SnapshotInfo,
+}
+
+/// リクエストごとに最新の AppState を取り出せる共有ハンドル
+#[derive(Clone)]
+pub struct SharedState {
+    current: Arc<RwLock<Snapshot>>,
+}
+
+impl SharedState {
+    pub fn new(state: AppState) -> Self {
+        let info = SnapshotInfo::from_state(&state);
+        Self::with_info(state, info)
+    }
+
+    pub fn with_info(state: AppState, info: SnapshotInfo) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Snapshot {
+                state: Arc::new(state),
+                info,
+            })),
+        }
+    }
+
+    pub fn load(&self) -> Arc<AppState> {
+        self.current.read().unwrap().state.clone()
+    }
+
+    pub fn info(&self) -> SnapshotInfo {
+        self.current.read().unwrap().info.clone()
+    }
+
+    /// 新しい状態に差し替え、それまでの状態を返す
+    pub fn replace(&self, state: AppState, info: SnapshotInfo) -> Arc<AppState> {
+        let mut current = self.current.write().unwrap();
+        current.info = info;
+        std::mem::replace(&mut current.state, Arc::new(state))
+    }
+
+    /// データディレクトリを読み直して差し替える。失敗した場合は現在の状態を保つ
+    pub async fn reload_from(
+        &self,
+        source: &DataSource,
+    ) -> Result<SnapshotInfo, Box<dyn Error + Send + Sync>> {
+        let (state, info) = load_snapshot(source).await?;
+        if state.problems.is_empty() {
+            return Err(format!("{} has no problems", source.dir.display()).into());
+        }
+        let before = self.replace(state, info.clone()).problems.len();
+        println!("Reloaded problems: {} -> {}", before, info.problem_count);
+
+        Ok(info)
+    }
+}
+
+type Fingerprint = Option<[(SystemTime, u64); 2]>;
+
+/// データファイルの更新を検知して SharedState を差し替える
+pub struct DataReloader {
+    source: DataSource,
+    state: SharedState,
+    fingerprint: Fingerprint,
+}
+
+impl DataReloader {
+    pub fn new(source: DataSource, state: SharedState) -> Self {
+        let fingerprint = fingerprint(&source.dir);
+
+        Self {
+            source,
+            state,
+            fingerprint,
+        }
+    }
+
+    /// ファイルが変わっていれば読み直す。差し替えた場合は true を返す
+    pub async fn check(&mut self) -> bool {
+        let fingerprint = fingerprint(&self.source.dir);
+        if fingerprint.is_none() || fingerprint == self.fingerprint {
+            return false;
+        }
+        self.fingerprint = fingerprint;
+
+        match self.state.reload_from(&self.source).await {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("Reload failed, keeping current problems: {}", e);
+                false
+            }
+        }
+    }
+
+    pub fn spawn(mut self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                self.check().await;
+            }
+        })
+    }
+}
+
+fn fingerprint(dir: &Path) -> Fingerprint {
+    let (problems_path, problem_models_path) = api::data_file_paths(dir);
+    let stat = |path: PathBuf| {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    };
+
+    Some([stat(problems_path)?, stat(problem_models_path)?])
+}
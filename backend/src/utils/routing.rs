@@ -1,9 +1,11 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc};
 use core::prelude::v1::derive;
 use hyper::{header, Body, Request, Response, StatusCode};
-use rand::seq::IteratorRandom;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::convert::{From, Infallible};
 use std::iter::Iterator;
 use std::option::Option::None;
@@ -12,59 +14,103 @@ use std::string::{String, ToString};
 use std::sync::Arc;
 use std::vec::Vec;
 
-use crate::utils::api::{Problem, ProblemModel};
-
-const MIN_DIFFICULTY: f64 = 0.0;
-const MAX_EXCLUDED_PROBLEMS: usize = 20;
-
+use crate::utils::api::{fnv1a, Problem, ProblemModel, FNV_OFFSET_BASIS};
+use crate::utils::daily::DailyProblems;
+use crate::utils::error::{ApiError, ErrorCode};
+use crate::utils::i18n::{Lang, Message};
+use crate::utils::pick::parse_pick_request;
+use crate::utils::problem_query::{ProblemQuery, UnknownDifficulty, MIN_DIFFICULTY};
+use crate::utils::query::{
+    apply_exclusions, collect_candidates, paginate, parse_bucket_width, parse_fields,
+    parse_optional_u32, parse_optional_u64, parse_page_size, parse_problem_filter, parse_sort,
+    problem_stats, select_candidates, select_candidates_where, task_url, ModelResponse,
+    ProblemFilter, ProblemResponse,
+};
+use crate::utils::saved::{saved_router, SavedProblems};
+use crate::utils::session::{
+    is_valid_session_id, session_id_from_request, HistoryEntry, SessionStore, SESSION_COOKIE,
+    SESSION_HEADER,
+};
+use crate::utils::submissions::SolvedProblems;
+
+pub(crate) const MAX_PICK_COUNT: usize = 50;
+const MAX_CONTEST_SET_BANDS: usize = 10;
+// JavaScript の Number で誤差なく扱える最大の整数
+pub(crate) const MAX_SEED: u64 = (1 << 53) - 1;
+// 日替わり問題の日付は既定で JST (UTC+9) で決める
+const DEFAULT_DAILY_UTC_OFFSET_SECS: i32 = 9 * 60 * 60;
+/// 解いた問題を一部しか除外できていないことを示すヘッダー
+pub const SOLVED_COMPLETE_HEADER: &str = "x-solved-complete";
+// JSON で受け取るリクエストボディの上限
+const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// 問題データ。ID の索引を作るため、`AppState::new` で作る
 #[derive(Clone)]
 pub struct AppState {
     pub problems: Vec<Problem>,
     pub problem_models: HashMap<String, ProblemModel>,
+    // 問題 ID から problems の位置を引く
+    problem_index: HashMap<String, usize>,
 }
 
-#[derive(Serialize)]
-enum Contest {
-    ABC,
-    ARC,
-    AGC,
-    Other,
-    Prefix(String),
-}
-
-impl Contest {
-    fn from_id(id: &str) -> Self {
-        if id.starts_with("abc") {
-            Contest::ABC
-        } else if id.starts_with("arc") {
-            Contest::ARC
-        } else if id.starts_with("agc") {
-            Contest::AGC
-        } else if id == "other" || id == "others" {
-            Contest::Other
-        } else {
-            Contest::Prefix(id.to_string())
+impl AppState {
+    /// 問題データから ID の索引を作る。`problems` を書き換えるときは作り直す
+    pub fn new(problems: Vec<Problem>, problem_models: HashMap<String, ProblemModel>) -> Self {
+        // ID が重複している場合は先に現れた問題を使う
+        let problem_index = problems
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, problem)| (problem.id.clone(), index))
+            .collect();
+
+        Self {
+            problems,
+            problem_models,
+            problem_index,
         }
     }
+
+    pub fn problem(&self, problem_id: &str) -> Option<&Problem> {
+        self.problem_index
+            .get(problem_id)
+            .map(|&index| &self.problems[index])
+    }
+}
+
+/// 問題データとは別に、リクエストをまたいで共有するサービス
+#[derive(Clone, Default)]
+pub struct Services {
+    /// 未設定の場合は `user` パラメータを受け付けない
+    pub solved_problems: Option<Arc<SolvedProblems>>,
+    pub sessions: Arc<SessionStore>,
+    pub daily_problems: Arc<DailyProblems>,
+    pub saved_problems: Arc<SavedProblems>,
 }
 
 #[derive(Serialize)]
-struct ProblemResponse {
-    id: String,
-    contest_id: String,
-    name: String,
-    difficulty: Option<f64>,
+struct ProblemListResponse {
+    total: usize,
+    problems: Vec<ProblemResponse>,
+    next_cursor: Option<String>,
 }
 
 #[derive(Serialize)]
-struct ErrorResponse {
-    message: String,
+struct SessionResponse {
+    session_id: String,
 }
 
-fn bad_request(message: &str) -> Response<Body> {
-    let mut res = Response::new(Body::from(message.to_string()));
-    *res.status_mut() = StatusCode::BAD_REQUEST;
-    with_cors_headers(res)
+#[derive(Serialize)]
+struct SessionHistoryResponse {
+    session_id: String,
+    history: Vec<HistoryEntry>,
+}
+
+#[derive(Serialize)]
+struct DailyProblemResponse {
+    #[serde(flatten)]
+    problem: ProblemResponse,
+    date: String,
 }
 
 async fn get_parameter(req: &Request<Body>) -> HashMap<String, String> {
@@ -74,77 +120,57 @@ async fn get_parameter(req: &Request<Body>) -> HashMap<String, String> {
         .collect()
 }
 
-fn parse_optional_f64(params: &HashMap<String, String>, key: &str) -> Result<Option<f64>, String> {
-    params
-        .get(key)
-        .map(|value| {
-            let value = value.trim();
-            if value.is_empty() {
-                return Ok(None);
-            }
-
-            value
-                .parse::<f64>()
-                .ok()
-                .filter(|value| value.is_finite())
-                .map(Some)
-                .ok_or_else(|| format!("'{}' must be a number.", key))
-        })
-        .transpose()
-        .map(Option::flatten)
-}
+fn parse_difficulty_bands(params: &HashMap<String, String>) -> Result<Vec<(f64, f64)>, ApiError> {
+    let invalid = || {
+        ApiError::invalid(
+            ErrorCode::InvalidValue,
+            "bands",
+            Message::InvalidBands,
+        )
+    };
 
-fn parse_optional_u32(params: &HashMap<String, String>, key: &str) -> Result<Option<u32>, String> {
-    params
-        .get(key)
-        .map(|value| {
-            let value = value.trim();
-            if value.is_empty() {
-                return Ok(None);
+    let bands = params
+        .get("bands")
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ApiError::invalid(ErrorCode::MissingParameter, "bands", Message::Required))?
+        .split(',')
+        .map(|band| {
+            let (min, max) = band.trim().split_once('-').ok_or_else(invalid)?;
+            let min = min.trim().parse::<f64>().map_err(|_| invalid())?;
+            let max = max.trim().parse::<f64>().map_err(|_| invalid())?;
+
+            if !min.is_finite() || !max.is_finite() || min < MIN_DIFFICULTY || min > max {
+                return Err(invalid());
             }
-
-            value
-                .parse::<u32>()
-                .map(Some)
-                .map_err(|_| format!("'{}' must be a positive integer.", key))
-        })
-        .transpose()
-        .map(Option::flatten)
-}
-
-fn parse_excluded_problem_ids(params: &HashMap<String, String>) -> Result<HashSet<String>, String> {
-    let excluded = params
-        .get("exclude")
-        .map(|value| {
-            value
-                .split(',')
-                .map(str::trim)
-                .filter(|id| !id.is_empty())
-                .map(ToString::to_string)
-                .collect::<HashSet<String>>()
+            Ok((min, max))
         })
-        .unwrap_or_default();
+        .collect::<Result<Vec<(f64, f64)>, ApiError>>()?;
 
-    if excluded.len() > MAX_EXCLUDED_PROBLEMS {
-        return Err(format!(
-            "'exclude' cannot contain more than {} problem IDs.",
-            MAX_EXCLUDED_PROBLEMS
+    if bands.len() > MAX_CONTEST_SET_BANDS {
+        return Err(ApiError::invalid(
+            ErrorCode::TooManyValues,
+            "bands",
+            Message::TooManyRanges(MAX_CONTEST_SET_BANDS),
         ));
     }
 
-    if excluded.iter().any(|id| {
-        id.len() > 100
-            || !id.chars().all(|character| {
-                character.is_ascii_alphanumeric() || matches!(character, '_' | '-')
-            })
-    }) {
-        return Err("'exclude' contains an invalid problem ID.".to_string());
-    }
+    Ok(bands)
+}
 
-    Ok(excluded)
+fn parse_seed(params: &HashMap<String, String>) -> Result<u64, ApiError> {
+    match parse_optional_u64(params, "seed")? {
+        Some(seed) if seed > MAX_SEED => Err(ApiError::invalid(
+            ErrorCode::OutOfRange,
+            "seed",
+            Message::Between(0, MAX_SEED),
+        )),
+        Some(seed) => Ok(seed),
+        None => Ok(rand::thread_rng().gen_range(0..=MAX_SEED)),
+    }
 }
 
-fn log(now: DateTime<Local>, method: &str, path: &str, status: StatusCode) {
+pub(crate) fn log(now: DateTime<Local>, method: &str, path: &str, status: StatusCode) {
     println!(
         "[{}] {} {} -> {}",
         now.format("%Y-%m-%d %H:%M:%S"),
@@ -154,59 +180,263 @@ fn log(now: DateTime<Local>, method: &str, path: &str, status: StatusCode) {
     );
 }
 
-fn with_cors_headers(mut res: Response<Body>) -> Response<Body> {
+pub(crate) fn with_cors_headers(mut res: Response<Body>) -> Response<Body> {
     let headers = res.headers_mut();
 
     headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*".parse().unwrap());
     headers.insert(
         header::ACCESS_CONTROL_ALLOW_METHODS,
-        "GET, POST, OPTIONS".parse().unwrap(),
+        "GET, POST, PUT, DELETE, OPTIONS".parse().unwrap(),
     );
     headers.insert(
         header::ACCESS_CONTROL_ALLOW_HEADERS,
-        "Content-Type".parse().unwrap(),
+        "Content-Type, X-Session-Id, X-User-Token".parse().unwrap(),
+    );
+    headers.insert(
+        header::ACCESS_CONTROL_EXPOSE_HEADERS,
+        "X-Session-Id, X-Solved-Complete, RateLimit-Limit, RateLimit-Remaining, RateLimit-Reset, Retry-After"
+            .parse()
+            .unwrap(),
     );
 
     res
 }
 
-fn standard_contest_id(problem_id: &str) -> Option<&str> {
-    let (contest_id, _) = problem_id.rsplit_once('_')?;
+/// JSON の本文と Content-Type を付けたレスポンスを作る
+pub(crate) fn json_response<T: Serialize>(status: StatusCode, body: &T) -> Response<Body> {
+    let mut res = Response::new(Body::from(serde_json::to_string(body).unwrap()));
+    *res.status_mut() = status;
+    res.headers_mut()
+        .insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+    with_cors_headers(res)
+}
 
-    for prefix in ["abc", "arc", "agc"] {
-        if let Some(number) = contest_id.strip_prefix(prefix) {
-            if number.parse::<u32>().is_ok() {
-                return Some(contest_id);
-            }
+fn parse_atcoder_user(params: &HashMap<String, String>) -> Result<Option<&str>, ApiError> {
+    let Some(user) = params.get("user").map(|user| user.trim()) else {
+        return Ok(None);
+    };
+    if user.is_empty() {
+        return Ok(None);
+    }
+
+    if user.len() > 32
+        || !user
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '_')
+    {
+        return Err(ApiError::invalid(
+            ErrorCode::InvalidValue,
+            "user",
+            Message::InvalidUser,
+        ));
+    }
+
+    Ok(Some(user))
+}
+
+/// `user` が指定されていれば、そのユーザーが AC した問題を候補から除外する。
+/// 提出を取得しきれず一部しか除外できていなければ false を返す
+async fn exclude_solved_problems(
+    params: &HashMap<String, String>,
+    services: &Services,
+    filter: &mut ProblemFilter,
+) -> Result<bool, ApiError> {
+    let Some(user) = parse_atcoder_user(params)? else {
+        return Ok(true);
+    };
+    let Some(solved_problems) = &services.solved_problems else {
+        return Err(ApiError::invalid(
+            ErrorCode::UnsupportedParameter,
+            "user",
+            Message::UnsupportedUser,
+        ));
+    };
+
+    match solved_problems.solved(user).await {
+        Ok(solved) => {
+            filter.query.exclude_solved(solved.problems);
+            Ok(solved.complete)
+        }
+        Err(e) => {
+            eprintln!("Failed to fetch submissions of {}: {}", user, e);
+            Err(ApiError::new(ErrorCode::UpstreamUnavailable, Message::SubmissionsUnavailable))
         }
     }
+}
 
-    None
+/// 解いた問題を一部しか除外できていない場合に X-Solved-Complete: false を付ける
+fn with_solved_complete(mut res: Response<Body>, complete: bool) -> Response<Body> {
+    if !complete {
+        res.headers_mut()
+            .insert(SOLVED_COMPLETE_HEADER, "false".parse().unwrap());
+    }
+    res
 }
 
-fn canonical_contest_id<'a>(problem_id: &'a str, contest_id: &'a str) -> &'a str {
-    standard_contest_id(problem_id).unwrap_or(contest_id)
+/// リクエストからセッション ID を取り出す。発行済みでない ID はエラーにする
+fn resolve_session(
+    req: &Request<Body>,
+    params: &HashMap<String, String>,
+    services: &Services,
+) -> Result<Option<String>, ApiError> {
+    let Some(session_id) = session_id_from_request(req, params) else {
+        return Ok(None);
+    };
+
+    if !is_valid_session_id(&session_id) {
+        return Err(ApiError::invalid(
+            ErrorCode::InvalidValue,
+            "session",
+            Message::InvalidSession,
+        ));
+    }
+    if !services.sessions.contains(&session_id) {
+        return Err(ApiError::invalid(
+            ErrorCode::SessionNotFound,
+            "session",
+            Message::SessionNotFound,
+        ));
+    }
+
+    Ok(Some(session_id))
 }
 
-fn standard_contest_number(problem_id: &str) -> Option<u32> {
-    let contest_id = standard_contest_id(problem_id)?;
+/// セッションで過去にピックした問題を候補から除外する
+fn exclude_session_history(services: &Services, session_id: &str, filter: &mut ProblemFilter) {
+    let history = services.sessions.history(session_id).unwrap_or_default();
+    filter
+        .query
+        .exclude(history.into_iter().map(|entry| entry.problem_id));
+}
 
-    for prefix in ["abc", "arc", "agc"] {
-        if let Some(number) = contest_id.strip_prefix(prefix) {
-            return number.parse().ok();
+/// 候補から問題を選んで返す。セッションがあれば選んだ問題を履歴に記録する
+fn pick_response(
+    candidates: Result<Vec<ProblemResponse>, ApiError>,
+    services: &Services,
+    session_id: Option<&str>,
+    count: Option<usize>,
+    seed: u64,
+    lang: Lang,
+) -> Response<Body> {
+    let candidates = match candidates {
+        Ok(candidates) => candidates,
+        Err(error) => return error.into_response(lang),
+    };
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let selected = match count {
+        Some(count) if candidates.len() < count => {
+            return ApiError::invalid(
+                ErrorCode::NotEnoughCandidates,
+                "count",
+                Message::NotEnoughCandidates {
+                    found: candidates.len(),
+                    requested: count,
+                },
+            )
+            .into_response(lang)
         }
+        Some(count) => candidates
+            .choose_multiple(&mut rng, count)
+            .map(|problem| ProblemResponse {
+                seed: Some(seed),
+                ..problem.clone()
+            })
+            .collect::<Vec<ProblemResponse>>(),
+        None => vec![ProblemResponse {
+            seed: Some(seed),
+            ..candidates.choose(&mut rng).unwrap().clone()
+        }],
+    };
+
+    if let Some(session_id) = session_id {
+        services.sessions.record(
+            session_id,
+            selected.iter().map(|problem| problem.id.clone()),
+        );
     }
 
-    None
+    let body = match count {
+        Some(_) => serde_json::to_string(&selected).unwrap(),
+        None => serde_json::to_string(&selected[0]).unwrap(),
+    };
+
+    with_cors_headers(Response::new(Body::from(body)))
+}
+
+fn parse_daily_date(params: &HashMap<String, String>, now: DateTime<Utc>) -> Result<NaiveDate, ApiError> {
+    let offset = match params.get("tz").map(|tz| tz.trim()) {
+        None | Some("") => FixedOffset::east_opt(DEFAULT_DAILY_UTC_OFFSET_SECS).unwrap(),
+        Some(tz) if tz.eq_ignore_ascii_case("utc") || tz == "Z" => FixedOffset::east_opt(0).unwrap(),
+        Some(tz) if tz.eq_ignore_ascii_case("jst") => {
+            FixedOffset::east_opt(DEFAULT_DAILY_UTC_OFFSET_SECS).unwrap()
+        }
+        Some(tz) => tz
+            .parse::<FixedOffset>()
+            .map_err(|_| {
+                ApiError::invalid(
+                    ErrorCode::InvalidValue,
+                    "tz",
+                    Message::InvalidTimeZone,
+                )
+            })?,
+    };
+
+    let today = now.with_timezone(&offset).date_naive();
+    let date = match params.get("date").map(|date| date.trim()) {
+        None | Some("") => return Ok(today),
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| {
+                ApiError::invalid(
+                    ErrorCode::InvalidValue,
+                    "date",
+                    Message::InvalidDate,
+                )
+            })?,
+    };
+
+    Ok(date)
+}
+
+/// 日付と絞り込み条件から日替わり問題を識別するキーを作る
+fn daily_key(date: NaiveDate, query: &ProblemQuery) -> String {
+    format!("{}&{}", date.format("%Y-%m-%d"), query.filter_key())
+}
+
+/// 上限を超えたら読むのをやめ、Err を返す
+pub(crate) async fn read_body(body: Body) -> Result<Vec<u8>, ApiError> {
+    use hyper::body::HttpBody;
+
+    let mut body = body;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk
+            .map_err(|_| ApiError::new(ErrorCode::InvalidBody, Message::BodyReadFailed))?;
+        if bytes.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(ApiError::new(ErrorCode::BodyTooLarge, Message::BodyTooLarge(MAX_BODY_BYTES)));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
 }
 
 pub async fn router(
     req: Request<Body>,
     state: Arc<AppState>,
+) -> Result<Response<Body>, Infallible> {
+    router_with_services(req, state, Services::default()).await
+}
+
+pub async fn router_with_services(
+    req: Request<Body>,
+    state: Arc<AppState>,
+    services: Services,
 ) -> Result<Response<Body>, Infallible> {
     let now = Local::now();
     let path = req.uri().path().to_string();
     let method = req.method().to_string();
+    let lang = Lang::from_request(&req);
 
     let res = match (req.method(), req.uri().path()) {
         (&hyper::Method::OPTIONS, _) => {
@@ -217,151 +447,383 @@ pub async fn router(
         (&hyper::Method::GET, "/") => {
             let params: HashMap<String, String> = get_parameter(&req).await;
 
-            let min: f64 = match parse_optional_f64(&params, "min") {
-                Ok(min) => min.unwrap_or(MIN_DIFFICULTY),
-                Err(message) => return Ok(bad_request(&message)),
+            let mut filter = match parse_problem_filter(&params) {
+                Ok(filter) => filter,
+                Err(error) => return Ok(error.into_response(lang)),
             };
-            let max: f64 = match parse_optional_f64(&params, "max") {
-                Ok(max) => max.unwrap_or(f64::INFINITY),
-                Err(message) => return Ok(bad_request(&message)),
+            let seed: u64 = match parse_seed(&params) {
+                Ok(seed) => seed,
+                Err(error) => return Ok(error.into_response(lang)),
             };
-            let allows_unknown_difficulty =
-                !params.contains_key("min") && !params.contains_key("max");
-
-            let contest_filters: Vec<Contest> = params
-                .get("contest")
-                .map(|s| {
-                    s.split(',')
-                        .filter_map(|id| {
-                            let id = id.trim();
-                            (!id.is_empty()).then(|| Contest::from_id(id))
-                        })
-                        .collect::<Vec<Contest>>()
-                })
-                .unwrap_or_default();
-
-            let contest_from: Option<u32> = match parse_optional_u32(&params, "contest_from") {
-                Ok(contest_from) => contest_from,
-                Err(message) => return Ok(bad_request(&message)),
-            };
-            let contest_to: Option<u32> = match parse_optional_u32(&params, "contest_to") {
-                Ok(contest_to) => contest_to,
-                Err(message) => return Ok(bad_request(&message)),
+            let count: Option<usize> = match parse_optional_u32(&params, "count") {
+                Ok(Some(count)) if count == 0 || count as usize > MAX_PICK_COUNT => {
+                    return Ok(ApiError::invalid(
+                        ErrorCode::OutOfRange,
+                        "count",
+                        Message::Between(1, MAX_PICK_COUNT as u64),
+                    )
+                    .into_response(lang))
+                }
+                Ok(count) => count.map(|count| count as usize),
+                Err(error) => return Ok(error.into_response(lang)),
             };
-            let excluded_problem_ids = match parse_excluded_problem_ids(&params) {
-                Ok(excluded) => excluded,
-                Err(message) => return Ok(bad_request(&message)),
+
+            let solved_complete = match exclude_solved_problems(&params, &services, &mut filter).await {
+                Ok(complete) => complete,
+                Err(error) => return Ok(error.into_response(lang)),
             };
 
-            if min > max {
-                return Ok(bad_request("'min' cannot be greater than 'max'."));
+            let session_id = match resolve_session(&req, &params, &services) {
+                Ok(session_id) => session_id,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            if let Some(session_id) = &session_id {
+                exclude_session_history(&services, session_id, &mut filter);
             }
 
-            if min < MIN_DIFFICULTY {
-                return Ok(bad_request("'min' cannot be less than 0."));
-            }
+            let candidates = select_candidates(&state, &filter);
+            let res = pick_response(candidates, &services, session_id.as_deref(), count, seed, lang);
+            Ok(with_solved_complete(res, solved_complete))
+        }
+
+        (&hyper::Method::POST, "/pick") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
 
-            if contest_from
-                .zip(contest_to)
-                .is_some_and(|(from, to)| from > to)
+            let session_id = match resolve_session(&req, &params, &services) {
+                Ok(session_id) => session_id,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let request = match read_body(req.into_body())
+                .await
+                .and_then(|bytes| parse_pick_request(&bytes))
             {
-                return Ok(bad_request(
-                    "'contest_from' cannot be greater than 'contest_to'.",
-                ));
+                Ok(request) => request,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let mut filter = request.filter;
+            let seed = request
+                .seed
+                .unwrap_or_else(|| rand::thread_rng().gen_range(0..=MAX_SEED));
+
+            // user の検証と解いた問題の取得はクエリパラメータの場合と共通にする
+            let user_params = HashMap::from_iter(request.user.map(|user| ("user".to_string(), user)));
+            let solved_complete = match exclude_solved_problems(&user_params, &services, &mut filter).await {
+                Ok(complete) => complete,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            if let Some(session_id) = &session_id {
+                exclude_session_history(&services, session_id, &mut filter);
             }
 
-            let candidates = state
-                .problems
-                .iter()
-                .filter(|p| {
-                    let contest_id = canonical_contest_id(&p.id, &p.contest_id);
+            let candidates = select_candidates_where(&state, &filter, |problem, model| {
+                request.rule.matches(problem, model)
+            });
+            let res = pick_response(
+                candidates,
+                &services,
+                session_id.as_deref(),
+                request.count,
+                seed,
+                lang,
+            );
+            Ok(with_solved_complete(res, solved_complete))
+        }
+
+        (&hyper::Method::GET, "/daily") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+
+            let mut filter = match parse_problem_filter(&params) {
+                Ok(filter) => filter,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            // 全員が同じ問題を見られるよう、履歴による除外は適用しない
+            filter.query.excluded_problem_ids.clear();
+
+            let date = match parse_daily_date(&params, now.with_timezone(&Utc)) {
+                Ok(date) => date,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            let candidates = match select_candidates(&state, &filter) {
+                Ok(candidates) => candidates,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
 
-                    if contest_filters.is_empty() {
-                        return true;
+            // 一度出題した問題は、候補に残っている限り同じ日・同じ条件で返し続ける
+            let key = daily_key(date, &filter.query);
+            let recorded = services.daily_problems.get(&key);
+            let problem = match recorded
+                .as_ref()
+                .and_then(|problem_id| candidates.iter().find(|problem| &problem.id == problem_id))
+            {
+                Some(problem) => problem.clone(),
+                None => {
+                    let mut rng = ChaCha8Rng::seed_from_u64(fnv1a(FNV_OFFSET_BASIS, key.as_bytes()));
+                    let problem = candidates.choose(&mut rng).unwrap().clone();
+                    // 記録済みの問題が候補から外れただけなら、記録は書き換えない
+                    if recorded.is_none() {
+                        services.daily_problems.record(date, &key, &problem.id);
                     }
-                    contest_filters.iter().any(|filter| match filter {
-                        Contest::ABC => contest_id.starts_with("abc"),
-                        Contest::ARC => contest_id.starts_with("arc"),
-                        Contest::AGC => contest_id.starts_with("agc"),
-                        Contest::Other => {
-                            !contest_id.starts_with("abc")
-                                && !contest_id.starts_with("arc")
-                                && !contest_id.starts_with("agc")
-                        }
-                        Contest::Prefix(s) => contest_id.starts_with(s),
-                    })
-                })
-                .filter(|p| {
-                    let Some(number) = standard_contest_number(&p.id) else {
-                        return contest_from.is_none() && contest_to.is_none();
-                    };
-
-                    contest_from.is_none_or(|from| number >= from)
-                        && contest_to.is_none_or(|to| number <= to)
-                })
-                .filter_map(|p| {
-                    state
-                        .problem_models
-                        .get(&p.id)
-                        .and_then(|m| match m.difficulty {
-                            Some(diff) if min <= diff && diff <= max => Some(ProblemResponse {
-                                id: p.id.clone(),
-                                contest_id: canonical_contest_id(&p.id, &p.contest_id).to_string(),
-                                name: p.name.clone(),
-                                difficulty: Some(diff),
-                            }),
-                            None if allows_unknown_difficulty => Some(ProblemResponse {
-                                id: p.id.clone(),
-                                contest_id: canonical_contest_id(&p.id, &p.contest_id).to_string(),
-                                name: p.name.clone(),
-                                difficulty: None,
-                            }),
-                            _ => None,
-                        })
-                })
-                .collect::<Vec<ProblemResponse>>();
-
-            let had_candidates_before_exclusion = !candidates.is_empty();
-            let mut rng = rand::thread_rng();
-            let selected = candidates
-                .into_iter()
-                .filter(|problem| !excluded_problem_ids.contains(&problem.id))
-                .choose(&mut rng);
-
-            match selected {
-                Some(problem) => {
-                    let body = serde_json::to_string(&problem).unwrap();
-                    Ok(with_cors_headers(Response::new(Body::from(body))))
+                    problem
                 }
-                None => {
-                    let message = if had_candidates_before_exclusion
-                        && !excluded_problem_ids.is_empty()
-                    {
-                        "履歴内の問題を除外すると、条件に一致する問題がありません。除外をOFFにするか、履歴を削除してください"
-                    } else {
-                        "指定Diff範囲に該当する問題がありませんでした"
-                    };
-                    let error_body = serde_json::to_string(&ErrorResponse {
-                        message: message.to_string(),
-                    })
-                    .unwrap();
-
-                    let mut not_found = Response::new(Body::from(error_body));
-                    *not_found.status_mut() = StatusCode::NOT_FOUND;
-                    not_found
-                        .headers_mut()
-                        .insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
-
-                    Ok(with_cors_headers(not_found))
+            };
+            let daily = DailyProblemResponse {
+                problem,
+                date: date.format("%Y-%m-%d").to_string(),
+            };
+            let body = serde_json::to_string(&daily).unwrap();
+
+            Ok(with_cors_headers(Response::new(Body::from(body))))
+        }
+
+        (&hyper::Method::GET, "/contest-set") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+
+            let mut filter = match parse_problem_filter(&params) {
+                Ok(filter) => filter,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let bands = match parse_difficulty_bands(&params) {
+                Ok(bands) => bands,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let seed: u64 = match parse_seed(&params) {
+                Ok(seed) => seed,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            let solved_complete = match exclude_solved_problems(&params, &services, &mut filter).await {
+                Ok(complete) => complete,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            // 難易度帯ごとに min/max を差し替え、選んだ問題は以降の帯から除外する
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let mut selected: Vec<ProblemResponse> = Vec::with_capacity(bands.len());
+            for (min, max) in bands {
+                let query = filter
+                    .query
+                    .to_builder()
+                    .min(min)
+                    .max(max)
+                    .unknown_difficulty(UnknownDifficulty::Exclude)
+                    .exclude(selected.iter().map(|problem| problem.id.clone()))
+                    .build();
+                let band_filter = match query {
+                    Ok(query) => ProblemFilter {
+                        query,
+                        includes_model: filter.includes_model,
+                    },
+                    Err(error) => return Ok(ApiError::from(error).into_response(lang)),
+                };
+
+                let problem = match select_candidates(&state, &band_filter) {
+                    Ok(candidates) => candidates.choose(&mut rng).unwrap().clone(),
+                    Err(error) if error.code == ErrorCode::ModelUnavailable => {
+                        return Ok(error.into_response(lang))
+                    }
+                    Err(_) => {
+                        return Ok(ApiError::invalid(
+                            ErrorCode::NoCandidates,
+                            "bands",
+                            Message::NoCandidatesInBand(min, max),
+                        )
+                        .into_response(lang))
+                    }
+                };
+
+                selected.push(ProblemResponse {
+                    seed: Some(seed),
+                    ..problem
+                });
+            }
+
+            let body = serde_json::to_string(&selected).unwrap();
+            let res = with_cors_headers(Response::new(Body::from(body)));
+            Ok(with_solved_complete(res, solved_complete))
+        }
+
+        (&hyper::Method::POST, "/session") => {
+            let Some(session_id) = services.sessions.create() else {
+                return Ok(ApiError::new(ErrorCode::SessionLimitExceeded, Message::TooManySessions)
+                    .into_response(lang));
+            };
+            let body = serde_json::to_string(&SessionResponse {
+                session_id: session_id.clone(),
+            })
+            .unwrap();
+
+            let mut res = Response::new(Body::from(body));
+            *res.status_mut() = StatusCode::CREATED;
+            let headers = res.headers_mut();
+            headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+            headers.insert(SESSION_HEADER, session_id.parse().unwrap());
+            headers.insert(
+                header::SET_COOKIE,
+                format!(
+                    "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+                    SESSION_COOKIE,
+                    session_id,
+                    services.sessions.retention().as_secs()
+                )
+                .parse()
+                .unwrap(),
+            );
+
+            Ok(with_cors_headers(res))
+        }
+
+        (&hyper::Method::GET, "/session/history") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+
+            let session_id = match resolve_session(&req, &params, &services) {
+                Ok(Some(session_id)) => session_id,
+                Ok(None) => {
+                    return Ok(ApiError::invalid(
+                        ErrorCode::MissingParameter,
+                        "session",
+                        Message::Required,
+                    )
+                    .into_response(lang))
                 }
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let history = services.sessions.history(&session_id).unwrap_or_default();
+            let body = serde_json::to_string(&SessionHistoryResponse {
+                session_id,
+                history,
+            })
+            .unwrap();
+
+            let mut res = Response::new(Body::from(body));
+            res.headers_mut()
+                .insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+            Ok(with_cors_headers(res))
+        }
+
+        (&hyper::Method::DELETE, "/session/history") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+
+            let session_id = match resolve_session(&req, &params, &services) {
+                Ok(Some(session_id)) => session_id,
+                Ok(None) => {
+                    return Ok(ApiError::invalid(
+                        ErrorCode::MissingParameter,
+                        "session",
+                        Message::Required,
+                    )
+                    .into_response(lang))
+                }
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            services.sessions.clear(&session_id);
+
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::NO_CONTENT;
+            Ok(with_cors_headers(res))
+        }
+
+        (&hyper::Method::GET, "/stats") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+
+            let mut filter = match parse_problem_filter(&params) {
+                Ok(filter) => filter,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let bucket_width = match parse_bucket_width(&params) {
+                Ok(bucket_width) => bucket_width,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            // GET / と同じ候補を数えるため、解いた問題と履歴の除外も適用する
+            let solved_complete = match exclude_solved_problems(&params, &services, &mut filter).await {
+                Ok(complete) => complete,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            match resolve_session(&req, &params, &services) {
+                Ok(Some(session_id)) => exclude_session_history(&services, &session_id, &mut filter),
+                Ok(None) => {}
+                Err(error) => return Ok(error.into_response(lang)),
             }
+
+            let candidates = apply_exclusions(collect_candidates(&state, &filter), &filter);
+            let body = serde_json::to_string(&problem_stats(&candidates, bucket_width)).unwrap();
+
+            let res = with_cors_headers(Response::new(Body::from(body)));
+            Ok(with_solved_complete(res, solved_complete))
+        }
+
+        (&hyper::Method::GET, "/problems") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+
+            let mut filter = match parse_problem_filter(&params) {
+                Ok(filter) => filter,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let sort = match parse_sort(&params) {
+                Ok(sort) => sort,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let limit = match parse_page_size(&params) {
+                Ok(limit) => limit,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            let solved_complete = match exclude_solved_problems(&params, &services, &mut filter).await {
+                Ok(complete) => complete,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            let candidates = apply_exclusions(collect_candidates(&state, &filter), &filter);
+            let cursor = params.get("cursor").map(String::as_str);
+            let page = match paginate(&state, candidates, sort, cursor, limit) {
+                Ok(page) => page,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+            let body = serde_json::to_string(&ProblemListResponse {
+                total: page.total,
+                problems: page.problems,
+                next_cursor: page.next_cursor,
+            })
+            .unwrap();
+
+            let res = with_cors_headers(Response::new(Body::from(body)));
+            Ok(with_solved_complete(res, solved_complete))
         }
 
-        _ => {
-            let mut not_found = Response::new(Body::from("404 Not Found"));
-            *not_found.status_mut() = StatusCode::NOT_FOUND;
-            Ok(with_cors_headers(not_found))
+        (&hyper::Method::GET, problem_path) if problem_path.starts_with("/problems/") => {
+            let params: HashMap<String, String> = get_parameter(&req).await;
+            let fields = match parse_fields(&params) {
+                Ok(fields) => fields,
+                Err(error) => return Ok(error.into_response(lang)),
+            };
+
+            let problem_id = &problem_path["/problems/".len()..];
+            let Some(problem) = state.problem(problem_id) else {
+                return Ok(ApiError::new(ErrorCode::ProblemNotFound, Message::ProblemNotFound).into_response(lang));
+            };
+
+            let model = state.problem_models.get(&problem.id);
+            let response = ProblemResponse::new(problem, model);
+            let response = ProblemResponse {
+                model: model
+                    .filter(|_| fields.contains(&"model"))
+                    .map(ModelResponse::from),
+                url: Some(task_url(&response.contest_id, &problem.id)),
+                ..response
+            };
+            let body = serde_json::to_string(&response).unwrap();
+
+            Ok(with_cors_headers(Response::new(Body::from(body))))
         }
+
+        (&hyper::Method::GET, "/saved") => Ok(saved_router(req, &state, &services.saved_problems).await),
+
+        (_, saved_path) if saved_path.starts_with("/saved/") => {
+            Ok(saved_router(req, &state, &services.saved_problems).await)
+        }
+
+        _ => Ok(ApiError::new(ErrorCode::NotFound, Message::NotFound).into_response(lang)),
     };
 
     let status = res
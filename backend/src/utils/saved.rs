@@ -0,0 +1,401 @@
+use chrono::{DateTime, Utc};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::utils::error::{ApiError, ErrorCode};
+use crate::utils::i18n::{Lang, Message};
+use crate::utils::problem_query::canonical_contest_id;
+use crate::utils::routing::{json_response, read_body, with_cors_headers, AppState};
+use crate::utils::storage::{sha256_hex, MemoryStorage, Record, Storage};
+
+pub const USER_TOKEN_HEADER: &str = "x-user-token";
+// フロントエンドのローカル保存と同じ上限
+pub const MAX_SAVED_PROBLEMS: usize = 255;
+/// 保存できるユーザートークンの数の上限
+pub const MAX_USER_TOKENS: usize = 10_000;
+// 保存先にはトークンそのものではなく、この接頭辞を付けたハッシュを書く
+const TOKEN_HASH_PREFIX: &str = "sha256:";
+// 保存した問題のメモとタグの上限
+const MAX_NOTE_CHARS: usize = 500;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_CHARS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedProblem {
+    pub problem_id: String,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// 保存の結果。新しく追加したか既存の保存を更新したかを区別する
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveOutcome {
+    Created(SavedProblem),
+    Updated(SavedProblem),
+    LimitExceeded,
+    /// 新しいトークンを受け付けられない
+    TooManyTokens,
+}
+
+/// ユーザートークンごとに保存した問題を保持する。トークンはハッシュにして扱う
+pub struct SavedProblems {
+    storage: Arc<dyn Storage>,
+    saved: Mutex<HashMap<String, Vec<SavedProblem>>>,
+}
+
+/// 保存先に書くトークンのハッシュ
+pub fn hash_user_token(token: &str) -> String {
+    format!("{}{}", TOKEN_HASH_PREFIX, sha256_hex(token.as_bytes()))
+}
+
+impl Default for SavedProblems {
+    fn default() -> Self {
+        Self {
+            storage: Arc::new(MemoryStorage::new()),
+            saved: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl SavedProblems {
+    /// 保存済みの記録から復元し、削除済みの記録を取り除く
+    pub fn with_storage(storage: Arc<dyn Storage>) -> io::Result<Self> {
+        let mut saved: HashMap<String, Vec<SavedProblem>> = HashMap::new();
+        for record in storage.load()? {
+            match record {
+                Record::ProblemSaved {
+                    token,
+                    problem_id,
+                    note,
+                    tags,
+                    saved_at,
+                } => {
+                    let problems = saved.entry(token).or_default();
+                    let problem = SavedProblem {
+                        problem_id,
+                        note,
+                        tags,
+                        saved_at,
+                    };
+                    match problems
+                        .iter_mut()
+                        .find(|saved| saved.problem_id == problem.problem_id)
+                    {
+                        Some(saved) => *saved = problem,
+                        None => problems.push(problem),
+                    }
+                }
+                Record::ProblemUnsaved { token, problem_id } => {
+                    if let Some(problems) = saved.get_mut(&token) {
+                        problems.retain(|saved| saved.problem_id != problem_id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        saved.retain(|_, problems| !problems.is_empty());
+
+        let records = saved
+            .iter()
+            .flat_map(|(token, problems)| {
+                problems.iter().map(|problem| Record::ProblemSaved {
+                    token: token.clone(),
+                    problem_id: problem.problem_id.clone(),
+                    note: problem.note.clone(),
+                    tags: problem.tags.clone(),
+                    saved_at: problem.saved_at,
+                })
+            })
+            .collect::<Vec<_>>();
+        storage.rewrite(&records)?;
+
+        Ok(Self {
+            storage,
+            saved: Mutex::new(saved),
+        })
+    }
+
+    fn persist(&self, record: Record) {
+        if let Err(e) = self.storage.append(&record) {
+            eprintln!("Failed to persist saved problem: {}", e);
+        }
+    }
+
+    /// 保存した順に返す
+    pub fn list(&self, token: &str) -> Vec<SavedProblem> {
+        self.saved
+            .lock()
+            .unwrap()
+            .get(&hash_user_token(token))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, token: &str, problem_id: &str) -> Option<SavedProblem> {
+        self.saved
+            .lock()
+            .unwrap()
+            .get(&hash_user_token(token))?
+            .iter()
+            .find(|saved| saved.problem_id == problem_id)
+            .cloned()
+    }
+
+    /// 問題を保存する。保存済みならメモとタグだけを更新し、保存日時は変えない
+    pub fn save(
+        &self,
+        token: &str,
+        problem_id: &str,
+        note: Option<String>,
+        tags: Vec<String>,
+    ) -> SaveOutcome {
+        let token = hash_user_token(token);
+        let mut saved = self.saved.lock().unwrap();
+        if !saved.contains_key(&token) && saved.len() >= MAX_USER_TOKENS {
+            return SaveOutcome::TooManyTokens;
+        }
+        let problems = saved.entry(token.clone()).or_default();
+
+        let outcome = match problems
+            .iter()
+            .position(|saved| saved.problem_id == problem_id)
+        {
+            Some(index) => {
+                problems[index].note = note;
+                problems[index].tags = tags;
+                SaveOutcome::Updated(problems[index].clone())
+            }
+            None if problems.len() >= MAX_SAVED_PROBLEMS => return SaveOutcome::LimitExceeded,
+            None => {
+                let problem = SavedProblem {
+                    problem_id: problem_id.to_string(),
+                    note,
+                    tags,
+                    saved_at: Utc::now(),
+                };
+                problems.push(problem.clone());
+                SaveOutcome::Created(problem)
+            }
+        };
+        match &outcome {
+            SaveOutcome::Created(problem) | SaveOutcome::Updated(problem) => {
+                self.persist(Record::ProblemSaved {
+                    token,
+                    problem_id: problem.problem_id.clone(),
+                    note: problem.note.clone(),
+                    tags: problem.tags.clone(),
+                    saved_at: problem.saved_at,
+                });
+            }
+            SaveOutcome::LimitExceeded | SaveOutcome::TooManyTokens => {}
+        }
+
+        outcome
+    }
+
+    /// 保存を解除する。保存していなければ false を返す
+    pub fn remove(&self, token: &str, problem_id: &str) -> bool {
+        let token = hash_user_token(token);
+        let mut saved = self.saved.lock().unwrap();
+        let Some(problems) = saved.get_mut(&token) else {
+            return false;
+        };
+        let len = problems.len();
+        problems.retain(|saved| saved.problem_id != problem_id);
+        if problems.len() == len {
+            return false;
+        }
+        if problems.is_empty() {
+            saved.remove(&token);
+        }
+
+        self.persist(Record::ProblemUnsaved {
+            token,
+            problem_id: problem_id.to_string(),
+        });
+
+        true
+    }
+}
+
+/// X-User-Token ヘッダーからユーザートークンを取り出す
+pub fn user_token_from_request(req: &Request<Body>) -> Option<String> {
+    req.headers()
+        .get(USER_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// 推測されにくいよう 16 文字以上の英数字・`-`・`_` に限る
+pub fn is_valid_user_token(token: &str) -> bool {
+    (16..=128).contains(&token.len())
+        && token
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '-' || character == '_')
+}
+
+/// 保存した問題に、現在の問題データから名前などを補って返す
+#[derive(Serialize)]
+struct SavedProblemResponse {
+    id: String,
+    contest_id: Option<String>,
+    name: Option<String>,
+    difficulty: Option<f64>,
+    note: Option<String>,
+    tags: Vec<String>,
+    saved_at: DateTime<Utc>,
+}
+
+impl SavedProblemResponse {
+    fn new(saved: SavedProblem, state: &AppState) -> Self {
+        let problem = state.problem(&saved.problem_id);
+
+        Self {
+            contest_id: problem
+                .map(|problem| canonical_contest_id(&problem.id, &problem.contest_id).to_string()),
+            name: problem.map(|problem| problem.name.clone()),
+            difficulty: state
+                .problem_models
+                .get(&saved.problem_id)
+                .and_then(|model| model.difficulty),
+            id: saved.problem_id,
+            note: saved.note,
+            tags: saved.tags,
+            saved_at: saved.saved_at,
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SaveProblemRequest {
+    note: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn parse_save_problem_request(bytes: &[u8]) -> Result<(Option<String>, Vec<String>), ApiError> {
+    let request = if bytes.iter().all(u8::is_ascii_whitespace) {
+        SaveProblemRequest::default()
+    } else {
+        serde_json::from_slice::<SaveProblemRequest>(bytes).map_err(|_| {
+            ApiError::new(ErrorCode::InvalidBody, Message::InvalidSaveBody)
+        })?
+    };
+
+    let note = request
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    if note
+        .as_ref()
+        .is_some_and(|note| note.chars().count() > MAX_NOTE_CHARS)
+    {
+        return Err(ApiError::invalid(
+            ErrorCode::OutOfRange,
+            "note",
+            Message::NoteTooLong(MAX_NOTE_CHARS),
+        ));
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in request.tags {
+        let tag = tag.trim().to_string();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS {
+            return Err(ApiError::invalid(
+                ErrorCode::OutOfRange,
+                "tags",
+                Message::TagLength(MAX_TAG_CHARS),
+            ));
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if tags.len() > MAX_TAGS {
+        return Err(ApiError::invalid(
+            ErrorCode::TooManyValues,
+            "tags",
+            Message::TooManyTags(MAX_TAGS),
+        ));
+    }
+
+    Ok((note, tags))
+}
+
+/// `/saved` と `/saved/{problem_id}` を処理する。X-User-Token ごとに保存先を分ける
+pub(crate) async fn saved_router(
+    req: Request<Body>,
+    state: &AppState,
+    saved_problems: &SavedProblems,
+) -> Response<Body> {
+    let lang = Lang::from_request(&req);
+    let Some(token) = user_token_from_request(&req) else {
+        return ApiError::new(ErrorCode::MissingUserToken, Message::MissingUserToken).into_response(lang);
+    };
+    if !is_valid_user_token(&token) {
+        return ApiError::new(ErrorCode::InvalidUserToken, Message::InvalidUserToken).into_response(lang);
+    }
+
+    let path = req.uri().path().to_string();
+    let Some(problem_id) = path.strip_prefix("/saved/") else {
+        let saved = saved_problems
+            .list(&token)
+            .into_iter()
+            .map(|saved| SavedProblemResponse::new(saved, state))
+            .collect::<Vec<_>>();
+        return json_response(StatusCode::OK, &saved);
+    };
+
+    match *req.method() {
+        hyper::Method::GET => match saved_problems.get(&token, problem_id) {
+            Some(saved) => json_response(StatusCode::OK, &SavedProblemResponse::new(saved, state)),
+            None => ApiError::new(ErrorCode::NotSaved, Message::NotSaved).into_response(lang),
+        },
+        hyper::Method::PUT => {
+            if state.problem(problem_id).is_none() {
+                return ApiError::new(ErrorCode::ProblemNotFound, Message::ProblemNotFound).into_response(lang);
+            }
+
+            let (note, tags) = match read_body(req.into_body())
+                .await
+                .and_then(|bytes| parse_save_problem_request(&bytes))
+            {
+                Ok(request) => request,
+                Err(error) => return error.into_response(lang),
+            };
+
+            match saved_problems.save(&token, problem_id, note, tags) {
+                SaveOutcome::Created(saved) => {
+                    json_response(StatusCode::CREATED, &SavedProblemResponse::new(saved, state))
+                }
+                SaveOutcome::Updated(saved) => {
+                    json_response(StatusCode::OK, &SavedProblemResponse::new(saved, state))
+                }
+                SaveOutcome::LimitExceeded => ApiError::new(
+                    ErrorCode::SavedLimitExceeded,
+                    Message::SavedLimitExceeded(MAX_SAVED_PROBLEMS),
+                )
+                .into_response(lang),
+                SaveOutcome::TooManyTokens => {
+                    ApiError::new(ErrorCode::SavedLimitExceeded, Message::TooManyUserTokens).into_response(lang)
+                }
+            }
+        }
+        hyper::Method::DELETE => {
+            if !saved_problems.remove(&token, problem_id) {
+                return ApiError::new(ErrorCode::NotSaved, Message::NotSaved).into_response(lang);
+            }
+
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::NO_CONTENT;
+            with_cors_headers(res)
+        }
+        _ => ApiError::new(ErrorCode::MethodNotAllowed, Message::MethodNotAllowed).into_response(lang),
+    }
+}
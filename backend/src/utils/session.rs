@@ -0,0 +1,329 @@
+use chrono::{DateTime, Utc};
+use hyper::{header, Body, Request};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::utils::storage::{MemoryStorage, Record, Storage};
+
+pub const SESSION_HEADER: &str = "x-session-id";
+pub const SESSION_COOKIE: &str = "session_id";
+const DEFAULT_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const MAX_RETENTION: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+/// 保持期間内のセッションの数の上限
+pub const MAX_SESSIONS: usize = 10_000;
+// 1 セッションが保持する履歴の上限
+const MAX_HISTORY_ENTRIES: usize = 5000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub problem_id: String,
+    pub picked_at: DateTime<Utc>,
+}
+
+struct Session {
+    history: Vec<HistoryEntry>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+}
+
+impl Session {
+    fn push_history(&mut self, problem_ids: Vec<String>, picked_at: DateTime<Utc>) {
+        self.last_seen_at = picked_at;
+        self.history
+            .extend(problem_ids.into_iter().map(|problem_id| HistoryEntry {
+                problem_id,
+                picked_at,
+            }));
+        let overflow = self.history.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        self.history.drain(..overflow);
+    }
+}
+
+/// セッションごとのピック履歴を保持する
+pub struct SessionStore {
+    retention: Duration,
+    storage: Arc<dyn Storage>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETENTION)
+    }
+}
+
+impl SessionStore {
+    /// 永続化しないセッションストアを作る
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            retention,
+            storage: Arc::new(MemoryStorage::new()),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 保存済みの記録からセッションを復元し、期限切れの記録を取り除く
+    pub fn with_storage(retention: Duration, storage: Arc<dyn Storage>) -> io::Result<Self> {
+        let store = Self {
+            retention,
+            storage,
+            sessions: Mutex::new(HashMap::new()),
+        };
+
+        let expires_before = store.expires_before(Utc::now());
+        let mut sessions = HashMap::new();
+        for record in store.storage.load()? {
+            match record {
+                Record::SessionCreated {
+                    session_id,
+                    created_at,
+                } => {
+                    sessions.insert(
+                        session_id,
+                        Session {
+                            history: Vec::new(),
+                            created_at,
+                            last_seen_at: created_at,
+                        },
+                    );
+                }
+                Record::ProblemsPicked {
+                    session_id,
+                    problem_ids,
+                    picked_at,
+                } => {
+                    if let Some(session) = sessions.get_mut(&session_id) {
+                        session.push_history(problem_ids, picked_at);
+                    }
+                }
+                Record::HistoryCleared {
+                    session_id,
+                    cleared_at,
+                } => {
+                    if let Some(session) = sessions.get_mut(&session_id) {
+                        session.history.clear();
+                        session.last_seen_at = cleared_at;
+                    }
+                }
+                _ => {}
+            }
+        }
+        sessions.retain(|_, session: &mut Session| {
+            session
+                .history
+                .retain(|entry| entry.picked_at >= expires_before);
+            session.last_seen_at >= expires_before
+        });
+
+        store.storage.rewrite(&compact(&sessions))?;
+        *store.sessions.lock().unwrap() = sessions;
+
+        Ok(store)
+    }
+
+    /// SESSION_RETENTION_SECS から保持期間を読む。未設定なら 30 日、上限は 365 日
+    pub fn retention_from_env() -> Result<Duration, String> {
+        match std::env::var("SESSION_RETENTION_SECS") {
+            Ok(secs) if !secs.trim().is_empty() => secs
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0 && *secs <= MAX_RETENTION.as_secs())
+                .map(Duration::from_secs)
+                .ok_or_else(|| {
+                    format!(
+                        "SESSION_RETENTION_SECS: must be a positive integer up to {}: {}",
+                        MAX_RETENTION.as_secs(),
+                        secs
+                    )
+                }),
+            _ => Ok(DEFAULT_RETENTION),
+        }
+    }
+
+    pub fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    fn expires_before(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        chrono::Duration::from_std(self.retention)
+            .ok()
+            .and_then(|retention| now.checked_sub_signed(retention))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    // 保存に失敗してもリクエストは失敗させず、メモリ上の状態で続ける
+    fn persist(&self, record: Record) {
+        if let Err(e) = self.storage.append(&record) {
+            eprintln!("Failed to persist session record: {}", e);
+        }
+    }
+
+    /// 新しいセッションを発行し、期限切れのセッションを破棄する。
+    /// 保持期間内のセッションが上限に達していれば None を返す
+    pub fn create(&self) -> Option<String> {
+        let session_id = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        let now = Utc::now();
+        let expires_before = self.expires_before(now);
+
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.last_seen_at >= expires_before);
+        if sessions.len() >= MAX_SESSIONS {
+            return None;
+        }
+        sessions.insert(
+            session_id.clone(),
+            Session {
+                history: Vec::new(),
+                created_at: now,
+                last_seen_at: now,
+            },
+        );
+        self.persist(Record::SessionCreated {
+            session_id: session_id.clone(),
+            created_at: now,
+        });
+
+        Some(session_id)
+    }
+
+    /// 保持期間内のセッションが存在するかどうか
+    pub fn contains(&self, session_id: &str) -> bool {
+        let expires_before = self.expires_before(Utc::now());
+
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .is_some_and(|session| session.last_seen_at >= expires_before)
+    }
+
+    /// 保持期間内の履歴を古い順に返す。セッションがなければ None を返す
+    pub fn history(&self, session_id: &str) -> Option<Vec<HistoryEntry>> {
+        let now = Utc::now();
+        let expires_before = self.expires_before(now);
+
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(session_id)?;
+        if session.last_seen_at < expires_before {
+            sessions.remove(session_id);
+            return None;
+        }
+
+        // 読むだけでは保存先に記録しないので、最終アクセス日時も更新しない
+        session
+            .history
+            .retain(|entry| entry.picked_at >= expires_before);
+        Some(session.history.clone())
+    }
+
+    /// ピックした問題を履歴に追加する。セッションがなければ false を返す
+    pub fn record<I: IntoIterator<Item = String>>(&self, session_id: &str, problem_ids: I) -> bool {
+        let now = Utc::now();
+        let problem_ids = problem_ids.into_iter().collect::<Vec<_>>();
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+
+        session.push_history(problem_ids.clone(), now);
+        self.persist(Record::ProblemsPicked {
+            session_id: session_id.to_string(),
+            problem_ids,
+            picked_at: now,
+        });
+
+        true
+    }
+
+    /// 履歴を削除する。セッションがなければ false を返す
+    pub fn clear(&self, session_id: &str) -> bool {
+        let now = Utc::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+
+        session.last_seen_at = now;
+        session.history.clear();
+        self.persist(Record::HistoryCleared {
+            session_id: session_id.to_string(),
+            cleared_at: now,
+        });
+
+        true
+    }
+}
+
+/// 現在のセッションを再現できる最小限の記録に変換する
+fn compact(sessions: &HashMap<String, Session>) -> Vec<Record> {
+    let mut records = Vec::new();
+    for (session_id, session) in sessions {
+        records.push(Record::SessionCreated {
+            session_id: session_id.clone(),
+            created_at: session.created_at,
+        });
+        for entries in session
+            .history
+            .chunk_by(|a, b| a.picked_at == b.picked_at)
+        {
+            records.push(Record::ProblemsPicked {
+                session_id: session_id.clone(),
+                problem_ids: entries.iter().map(|entry| entry.problem_id.clone()).collect(),
+                picked_at: entries[0].picked_at,
+            });
+        }
+
+        let last_picked_at = session.history.last().map(|entry| entry.picked_at);
+        if session.last_seen_at > last_picked_at.unwrap_or(session.created_at) {
+            records.push(Record::HistoryCleared {
+                session_id: session_id.clone(),
+                cleared_at: session.last_seen_at,
+            });
+        }
+    }
+
+    records
+}
+
+/// `session` パラメータ、X-Session-Id ヘッダー、session_id Cookie の順にセッション ID を探す
+pub fn session_id_from_request(
+    req: &Request<Body>,
+    params: &HashMap<String, String>,
+) -> Option<String> {
+    let from_param = params
+        .get("session")
+        .map(|session_id| session_id.trim().to_string());
+    let from_header = || {
+        req.headers()
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+    };
+    let from_cookie = || {
+        req.headers()
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == SESSION_COOKIE)
+            .map(|(_, value)| value.trim().to_string())
+    };
+
+    from_param
+        .or_else(from_header)
+        .or_else(from_cookie)
+        .filter(|session_id| !session_id.is_empty())
+}
+
+pub fn is_valid_session_id(session_id: &str) -> bool {
+    session_id.len() == 32
+        && session_id
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// 保存先ディレクトリの既定値 (DATA_DIR からの相対パス)
+pub const DEFAULT_STATE_DIR: &str = "state";
+
+/// 再起動後も残したい状態の変更を 1 件ずつ表す
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    SessionCreated {
+        session_id: String,
+        created_at: DateTime<Utc>,
+    },
+    ProblemsPicked {
+        session_id: String,
+        problem_ids: Vec<String>,
+        picked_at: DateTime<Utc>,
+    },
+    HistoryCleared {
+        session_id: String,
+        cleared_at: DateTime<Utc>,
+    },
+    ProblemSaved {
+        /// ユーザートークンのハッシュ (`sha256:<16 進数>`)
+        token: String,
+        problem_id: String,
+        note: Option<String>,
+        tags: Vec<String>,
+        saved_at: DateTime<Utc>,
+    },
+    ProblemUnsaved {
+        token: String,
+        problem_id: String,
+    },
+    DailyProblem {
+        date: NaiveDate,
+        /// 絞り込み条件から作ったキーのハッシュ
+        key: String,
+        problem_id: String,
+    },
+}
+
+/// 状態の保存先。ストアごとに別の保存先を使う
+pub trait Storage: Send + Sync {
+    /// 記録を末尾に追加する
+    fn append(&self, record: &Record) -> io::Result<()>;
+    /// これまでの記録を追加した順に返す
+    fn load(&self) -> io::Result<Vec<Record>>;
+    /// 記録全体を置き換える。不要になった記録の削除に使う
+    fn rewrite(&self, records: &[Record]) -> io::Result<()>;
+}
+
+/// プロセス内にだけ保持する。テストや永続化しない環境向け
+#[derive(Default)]
+pub struct MemoryStorage {
+    records: Mutex<Vec<Record>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn append(&self, record: &Record) -> io::Result<()> {
+        self.records.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+
+    fn load(&self) -> io::Result<Vec<Record>> {
+        Ok(self.records.lock().unwrap().clone())
+    }
+
+    fn rewrite(&self, records: &[Record]) -> io::Result<()> {
+        *self.records.lock().unwrap() = records.to_vec();
+        Ok(())
+    }
+}
+
+/// 1 行 1 記録の JSON Lines ファイルに追記する
+pub struct FileStorage {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Storage for FileStorage {
+    fn append(&self, record: &Record) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+
+    fn load(&self) -> io::Result<Vec<Record>> {
+        let _guard = self.lock.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // 書き込み途中で停止した行などは読み飛ばす
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => eprintln!(
+                    "Skipping broken record at {}:{}: {}",
+                    self.path.display(),
+                    index + 1,
+                    e
+                ),
+            }
+        }
+
+        Ok(records)
+    }
+
+    fn rewrite(&self, records: &[Record]) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut body = String::new();
+        for record in records {
+            body.push_str(&serde_json::to_string(record)?);
+            body.push('\n');
+        }
+
+        let file_name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp = self.path.with_file_name(format!(".{}.tmp", file_name));
+        fs::write(&temp, body)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+/// `<STATE_DIR>/<name>.jsonl` に保存する。STATE_DIR の既定値は `<data_dir>/state`
+pub fn storage_from_env(data_dir: &Path, name: &str) -> Arc<dyn Storage> {
+    let dir = std::env::var("STATE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir.join(DEFAULT_STATE_DIR));
+
+    Arc::new(FileStorage::new(dir.join(format!("{}.jsonl", name))))
+}
+
+/// 保存先に書く識別子の SHA-256 ハッシュ (16 進数)
+pub fn sha256_hex(data: &[u8]) -> String {
+    digest(&SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,273 @@
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const DEFAULT_SUBMISSIONS_API: &str = "https://kenkoooo.com/atcoder/atcoder-api/v3";
+/// AtCoder Problems API が 1 回に返す提出数の上限
+pub const SUBMISSIONS_PER_PAGE: usize = 500;
+// AtCoder Problems API へのリクエスト間隔
+const PAGE_INTERVAL: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+/// 1 リクエストで取得するページ数の上限。残りは次のリクエストで続きから取得する
+pub const MAX_PAGES_PER_REQUEST: usize = 3;
+/// キャッシュするユーザー数の上限
+pub const MAX_CACHED_USERS: usize = 1000;
+// 使われていないキャッシュを捨てるまでの時間
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+pub type SourceResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+pub type SourceFuture<'a, T> = Pin<Box<dyn Future<Output = SourceResult<T>> + Send + 'a>>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Submission {
+    pub epoch_second: u64,
+    pub problem_id: String,
+    pub result: String,
+}
+
+/// ユーザーの提出一覧を取得する先
+pub trait SubmissionSource: Send + Sync {
+    /// `from_second` 以降の提出を古い順に返す。`SUBMISSIONS_PER_PAGE` 件以上返した場合は続きがある
+    fn fetch<'a>(&'a self, user: &'a str, from_second: u64) -> SourceFuture<'a, Vec<Submission>>;
+}
+
+/// AtCoder Problems 互換の API から提出を 1 ページずつ取得する
+pub struct HttpSubmissionSource {
+    base_url: String,
+    client: reqwest::Client,
+    // 前回リクエストした時刻。ユーザーをまたいで PAGE_INTERVAL を空ける
+    requested_at: Mutex<Option<Instant>>,
+}
+
+impl HttpSubmissionSource {
+    pub fn new(base_url: &str) -> SourceResult<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent("atrp-backend")
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            requested_at: Mutex::new(None),
+        })
+    }
+}
+
+impl SubmissionSource for HttpSubmissionSource {
+    fn fetch<'a>(&'a self, user: &'a str, from_second: u64) -> SourceFuture<'a, Vec<Submission>> {
+        Box::pin(async move {
+            {
+                let mut requested_at = self.requested_at.lock().await;
+                if let Some(requested_at) = *requested_at {
+                    tokio::time::sleep(PAGE_INTERVAL.saturating_sub(requested_at.elapsed())).await;
+                }
+                *requested_at = Some(Instant::now());
+            }
+
+            let url = format!("{}/user/submissions", self.base_url);
+            let res = self
+                .client
+                .get(&url)
+                .query(&[("user", user), ("from_second", &from_second.to_string())])
+                .send()
+                .await?
+                .error_for_status()?;
+
+            Ok(serde_json::from_slice(&res.bytes().await?)?)
+        })
+    }
+}
+
+/// `<dir>/<user>.json` に保存された提出一覧を読む
+pub struct FileSubmissionSource {
+    dir: PathBuf,
+}
+
+impl FileSubmissionSource {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl SubmissionSource for FileSubmissionSource {
+    fn fetch<'a>(&'a self, user: &'a str, from_second: u64) -> SourceFuture<'a, Vec<Submission>> {
+        Box::pin(async move {
+            let path = self.dir.join(format!("{}.json", user));
+            if !path.exists() {
+                return Ok(Vec::new());
+            }
+
+            let submissions: Vec<Submission> = serde_json::from_str(&fs::read_to_string(path)?)?;
+            Ok(submissions
+                .into_iter()
+                .filter(|submission| submission.epoch_second >= from_second)
+                .collect())
+        })
+    }
+}
+
+/// ユーザーが AC した問題
+pub struct Solved {
+    pub problems: Arc<HashSet<String>>,
+    /// 提出をすべて取得し終えたか。false の場合は一部の問題が含まれていない
+    pub complete: bool,
+}
+
+#[derive(Default)]
+struct CachedSolved {
+    solved: Arc<HashSet<String>>,
+    next_from_second: u64,
+    // 取得しきれていない提出が残っているか
+    has_more: bool,
+    refreshed_at: Option<Instant>,
+}
+
+struct CacheEntry {
+    // 同じユーザーの取得が重ならないよう、ユーザーごとにロックする
+    solved: Arc<Mutex<CachedSolved>>,
+    used_at: Instant,
+}
+
+/// ユーザーごとの AC 済み問題を保持し、古くなったら差分だけ取得し直す
+pub struct SolvedProblems {
+    source: Arc<dyn SubmissionSource>,
+    refresh_interval: Duration,
+    cache: std::sync::Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl SolvedProblems {
+    pub fn new(source: Arc<dyn SubmissionSource>) -> Self {
+        Self::with_refresh_interval(source, DEFAULT_REFRESH_INTERVAL)
+    }
+
+    pub fn with_refresh_interval(source: Arc<dyn SubmissionSource>, refresh_interval: Duration) -> Self {
+        Self {
+            source,
+            refresh_interval,
+            cache: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// SUBMISSIONS_DIR が設定されていればローカルファイルを、なければ SUBMISSIONS_API を使う
+    pub fn from_env() -> SourceResult<Self> {
+        let source: Arc<dyn SubmissionSource> = match std::env::var("SUBMISSIONS_DIR") {
+            Ok(dir) => Arc::new(FileSubmissionSource::new(PathBuf::from(dir))),
+            Err(_) => {
+                let base_url = std::env::var("SUBMISSIONS_API")
+                    .unwrap_or_else(|_| DEFAULT_SUBMISSIONS_API.to_string());
+                Arc::new(HttpSubmissionSource::new(&base_url)?)
+            }
+        };
+
+        Ok(Self::new(source))
+    }
+
+    /// キャッシュしているユーザー数
+    pub fn cached_users(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    // ユーザーのキャッシュを取り出す。上限を超える場合は古いものから捨てる
+    fn entry(&self, user: &str) -> Arc<Mutex<CachedSolved>> {
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(entry) = cache.get_mut(user) {
+            entry.used_at = now;
+            return entry.solved.clone();
+        }
+
+        cache.retain(|_, entry| now.duration_since(entry.used_at) < CACHE_TTL);
+        while cache.len() >= MAX_CACHED_USERS {
+            let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.used_at)
+                .map(|(user, _)| user.clone())
+            else {
+                break;
+            };
+            cache.remove(&oldest);
+        }
+
+        let solved = Arc::new(Mutex::new(CachedSolved::default()));
+        cache.insert(
+            user.to_string(),
+            CacheEntry {
+                solved: solved.clone(),
+                used_at: now,
+            },
+        );
+        solved
+    }
+
+    /// AC 済みの問題を返す。提出が多い場合は `MAX_PAGES_PER_REQUEST` ページ分までの途中の結果を返す。
+    /// 途中のページで取得に失敗した場合は、それまでのページを反映してからエラーを返す
+    pub async fn solved(&self, user: &str) -> SourceResult<Solved> {
+        let entry = self.entry(user);
+        // 同じユーザーへの同時リクエストは、先に始めた取得の結果を待って使う
+        let mut cached = entry.lock().await;
+        let is_fresh = cached
+            .refreshed_at
+            .is_some_and(|refreshed_at| refreshed_at.elapsed() < self.refresh_interval);
+        if is_fresh && !cached.has_more {
+            return Ok(Solved {
+                problems: cached.solved.clone(),
+                complete: true,
+            });
+        }
+
+        let mut newly_solved = Vec::new();
+        let mut from_second = cached.next_from_second;
+        let mut has_more = true;
+        let mut result = Ok(());
+        for _ in 0..MAX_PAGES_PER_REQUEST {
+            let page = match self.source.fetch(user, from_second).await {
+                Ok(page) => page,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            has_more = page.len() >= SUBMISSIONS_PER_PAGE;
+            let Some(last) = page.iter().map(|submission| submission.epoch_second).max() else {
+                has_more = false;
+                break;
+            };
+
+            // 同じ秒の提出がページをまたぐことがあるため、最後の秒から取得し直す。
+            // 重なった提出は HashSet で除く。1 ページがすべて同じ秒なら先へ進める
+            from_second = if has_more && last == from_second { last + 1 } else { last };
+            newly_solved.extend(
+                page.into_iter()
+                    .filter(|submission| submission.result == "AC" && !cached.solved.contains(&submission.problem_id))
+                    .map(|submission| submission.problem_id),
+            );
+            if !has_more {
+                break;
+            }
+        }
+
+        if !newly_solved.is_empty() {
+            let mut merged = (*cached.solved).clone();
+            merged.extend(newly_solved);
+            cached.solved = Arc::new(merged);
+        }
+        cached.next_from_second = from_second;
+        result?;
+        cached.has_more = has_more;
+        cached.refreshed_at = Some(Instant::now());
+
+        Ok(Solved {
+            problems: cached.solved.clone(),
+            complete: !has_more,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use crate::utils::api::{self, Problem};
+
+// エラーメッセージに列挙する ID の上限
+const MAX_LISTED_IDS: usize = 10;
+
+/// データファイルの検証結果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub missing_files: Vec<String>,
+    pub duplicated_problem_ids: Vec<String>,
+    pub problems_without_models: Vec<String>,
+    pub models_without_problems: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.missing_files.is_empty()
+            && self.duplicated_problem_ids.is_empty()
+            && self.problems_without_models.is_empty()
+            && self.models_without_problems.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "problem data is valid");
+        }
+
+        write!(f, "problem data is invalid")?;
+        for (label, ids) in [
+            ("missing files", &self.missing_files),
+            ("duplicated problem ids", &self.duplicated_problem_ids),
+            ("problems without models", &self.problems_without_models),
+            ("models without problems", &self.models_without_problems),
+        ] {
+            if ids.is_empty() {
+                continue;
+            }
+
+            write!(f, "; {} ({}): ", label, ids.len())?;
+            write!(f, "{}", ids.iter().take(MAX_LISTED_IDS).cloned().collect::<Vec<_>>().join(", "))?;
+            if ids.len() > MAX_LISTED_IDS {
+                write!(f, ", ...")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ValidationReport {}
+
+/// データディレクトリに存在しないデータファイルを返す
+pub fn missing_data_files(dir: &Path) -> Vec<String> {
+    let (problems_path, problem_models_path) = api::data_file_paths(dir);
+
+    [problems_path, problem_models_path]
+        .into_iter()
+        .filter(|path| !path.exists())
+        .map(|path| path.display().to_string())
+        .collect()
+}
+
+/// 問題一覧と難易度モデルの整合性を検証する
+pub fn validate_problems<M>(problems: &[Problem], problem_models: &HashMap<String, M>) -> ValidationReport {
+    let mut seen = HashSet::new();
+    let mut duplicated_problem_ids = problems
+        .iter()
+        .filter(|problem| !seen.insert(problem.id.as_str()))
+        .map(|problem| problem.id.clone())
+        .collect::<Vec<String>>();
+    duplicated_problem_ids.sort();
+    duplicated_problem_ids.dedup();
+
+    let mut problems_without_models = seen
+        .iter()
+        .filter(|id| !problem_models.contains_key(**id))
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>();
+    problems_without_models.sort();
+
+    let mut models_without_problems = problem_models
+        .keys()
+        .filter(|id| !seen.contains(id.as_str()))
+        .cloned()
+        .collect::<Vec<String>>();
+    models_without_problems.sort();
+
+    ValidationReport {
+        missing_files: Vec::new(),
+        duplicated_problem_ids,
+        problems_without_models,
+        models_without_problems,
+    }
+}
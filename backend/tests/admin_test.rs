@@ -0,0 +1,151 @@
+mod common;
+
+use backend::utils::admin::{admin_router, AdminConfig};
+use backend::utils::api::DataSource;
+use backend::utils::reload::SharedState;
+use backend::utils::routing::AppState;
+use hyper::{Body, Method, StatusCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use common::{request_builder, temp_data_dir};
+
+fn data_dir_with_problems(name: &str) -> PathBuf {
+    let dir = temp_data_dir("admin", name);
+    fs::write(
+        dir.join("problems.json"),
+        r#"[{"id":"abc001_a","contest_id":"abc001","name":"A"},{"id":"abc001_b","contest_id":"abc001","name":"B"}]"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("problem-models.json"),
+        r#"{"abc001_a":{"difficulty":400},"abc001_b":{"difficulty":800}}"#,
+    )
+    .unwrap();
+
+    dir
+}
+
+async fn send(
+    method: Method,
+    path: &str,
+    token: Option<&str>,
+    state: SharedState,
+    config: AdminConfig,
+) -> (StatusCode, serde_json::Value) {
+    let mut req = request_builder(method, path);
+    if let Some(token) = token {
+        req = req.header("Authorization", format!("Bearer {token}"));
+    }
+
+    let res = admin_router(req.body(Body::empty()).unwrap(), state, Arc::new(config))
+        .await
+        .unwrap();
+
+    let status = res.status();
+    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    let body = serde_json::from_slice(&body_bytes).unwrap_or(serde_json::Value::Null);
+
+    (status, body)
+}
+
+fn empty_state() -> SharedState {
+    SharedState::new(AppState::new(Vec::new(), HashMap::new()))
+}
+
+fn config(dir: PathBuf) -> AdminConfig {
+    AdminConfig {
+        token: Some("secret".to_string()),
+        source: DataSource { dir, strict: true },
+    }
+}
+
+#[tokio::test]
+async fn test_admin_endpoints_are_disabled_without_token() {
+    let config = AdminConfig {
+        token: None,
+        ..config(PathBuf::from("data"))
+    };
+    let (status, _) = send(Method::GET, "/admin/status", Some(""), empty_state(), config).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_endpoints_reject_wrong_token() {
+    let dir = PathBuf::from("data");
+    for token in [None, Some("wrong")] {
+        let (status, body) =
+            send(Method::POST, "/admin/reload", token, empty_state(), config(dir.clone())).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["message"], "Invalid admin token.");
+    }
+}
+
+#[tokio::test]
+async fn test_admin_reload_swaps_state_and_reports_snapshot() {
+    let dir = data_dir_with_problems("reload");
+    let state = empty_state();
+
+    let (status, body) = send(
+        Method::POST,
+        "/admin/reload",
+        Some("secret"),
+        state.clone(),
+        config(dir.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["previous_problem_count"], 0);
+    assert_eq!(body["status"]["problem_count"], 2);
+    assert_eq!(state.load().problems.len(), 2);
+
+    let (status, body) = send(
+        Method::GET,
+        "/admin/status",
+        Some("secret"),
+        state.clone(),
+        config(dir.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["problem_count"], 2);
+    assert_eq!(body["model_count"], 2);
+    assert_eq!(body["content_hash"].as_str().unwrap().len(), 16);
+    assert!(body["loaded_at"].is_string());
+    assert!(body["problems_modified_at"].is_string());
+    assert!(body["problem_models_modified_at"].is_string());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_reload_failure_keeps_current_state() {
+    let dir = data_dir_with_problems("failure");
+    fs::write(dir.join("problems.json"), "not json").unwrap();
+
+    let state = SharedState::new(AppState::new(
+        vec![backend::utils::api::Problem {
+            id: "abc001_a".to_string(),
+            contest_id: "abc001".to_string(),
+            name: "A".to_string(),
+        }],
+        HashMap::new(),
+    ));
+
+    let (status, _) = send(
+        Method::POST,
+        "/admin/reload",
+        Some("secret"),
+        state.clone(),
+        config(dir.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(state.load().problems.len(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
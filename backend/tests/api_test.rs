@@ -1,4 +1,4 @@
-use backend::utils::api::{Problem, ProblemModel};
+use backend::utils::api::{predict_solve_probability, Problem, ProblemModel};
 use std::collections::HashMap;
 
 /// fetch_problem のモック版
@@ -21,11 +21,17 @@ pub async fn fetch_problem_mock() -> (Vec<Problem>, HashMap<String, ProblemModel
     let mut problem_models = HashMap::new();
     problem_models.insert(
         "abc_001".to_string(),
-        ProblemModel { difficulty: Some(400.0) },
+        ProblemModel {
+            difficulty: Some(400.0),
+            ..Default::default()
+        },
     );
     problem_models.insert(
         "def_002".to_string(),
-        ProblemModel { difficulty: Some(800.0) },
+        ProblemModel {
+            difficulty: Some(800.0),
+            ..Default::default()
+        },
     );
 
     (problems, problem_models)
@@ -45,4 +51,40 @@ async fn test_fetch_problem_mock() {
 
     let first_model = problem_models.get(&first.id).unwrap();
     assert_eq!(first_model.difficulty, Some(400.0));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_predict_solve_probability() {
+    let model = ProblemModel {
+        difficulty: Some(1200.0),
+        discrimination: Some(0.004),
+        ..Default::default()
+    };
+
+    assert_eq!(predict_solve_probability(&model, 1200.0), Some(0.5));
+    assert!(predict_solve_probability(&model, 1600.0).unwrap() > 0.8);
+    assert!(predict_solve_probability(&model, 800.0).unwrap() < 0.2);
+}
+
+#[test]
+fn test_predict_solve_probability_uses_unadjusted_low_difficulty() {
+    // 内部的な難易度 0 は表示上 400 / e に補正される
+    let model = ProblemModel {
+        difficulty: Some(400.0 / std::f64::consts::E),
+        discrimination: Some(0.004),
+        ..Default::default()
+    };
+
+    let probability = predict_solve_probability(&model, 400.0 / std::f64::consts::E).unwrap();
+    assert!((probability - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_predict_solve_probability_requires_discrimination() {
+    let model = ProblemModel {
+        difficulty: Some(1200.0),
+        ..Default::default()
+    };
+
+    assert_eq!(predict_solve_probability(&model, 1200.0), None);
+}
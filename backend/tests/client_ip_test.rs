@@ -0,0 +1,131 @@
+use backend::utils::client_ip::{rate_limit_key, ClientIpResolver, IpNet};
+use backend::utils::ratelimiter::{RateLimitConfig, RateLimiter};
+use hyper::header::HeaderMap;
+use std::net::IpAddr;
+use std::time::Instant;
+
+fn ip(value: &str) -> IpAddr {
+    value.parse().unwrap()
+}
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.append(*name, value.parse().unwrap());
+    }
+    headers
+}
+
+fn fly_resolver() -> ClientIpResolver {
+    ClientIpResolver::new(vec![
+        IpNet::parse("172.16.0.0/12").unwrap(),
+        IpNet::parse("fdaa::/16").unwrap(),
+    ])
+}
+
+#[test]
+fn test_ip_net_parse_and_contains() {
+    let net = IpNet::parse("10.1.2.3/8").unwrap();
+    assert!(net.contains(ip("10.255.0.1")));
+    assert!(!net.contains(ip("11.0.0.1")));
+    // IPv4 射影アドレスも IPv4 として扱う
+    assert!(net.contains(ip("::ffff:10.0.0.1")));
+
+    let single = IpNet::parse("192.0.2.1").unwrap();
+    assert!(single.contains(ip("192.0.2.1")));
+    assert!(!single.contains(ip("192.0.2.2")));
+
+    assert!(IpNet::parse("fdaa::/16").unwrap().contains(ip("fdaa:0:1::3")));
+    assert!(IpNet::parse("0.0.0.0/0").unwrap().contains(ip("203.0.113.9")));
+    assert!(IpNet::parse("10.0.0.0/33").is_err());
+    assert!(IpNet::parse("fdaa::/129").is_err());
+    assert!(IpNet::parse("example.com/8").is_err());
+}
+
+#[test]
+fn test_headers_are_ignored_from_untrusted_peer() {
+    let resolver = fly_resolver();
+    let spoofed = headers(&[
+        ("fly-client-ip", "198.51.100.1"),
+        ("x-forwarded-for", "198.51.100.2"),
+        ("forwarded", "for=198.51.100.3"),
+    ]);
+
+    assert_eq!(resolver.resolve(ip("203.0.113.7"), &spoofed), ip("203.0.113.7"));
+    // 信頼するプロキシが未設定ならヘッダーは常に使わない
+    assert_eq!(
+        ClientIpResolver::default().resolve(ip("172.16.0.5"), &spoofed),
+        ip("172.16.0.5")
+    );
+}
+
+#[test]
+fn test_fly_client_ip_from_trusted_proxy() {
+    let resolver = fly_resolver();
+    let headers = headers(&[
+        ("fly-client-ip", "203.0.113.7"),
+        ("x-forwarded-for", "198.51.100.1"),
+    ]);
+
+    assert_eq!(resolver.resolve(ip("172.19.0.2"), &headers), ip("203.0.113.7"));
+    assert_eq!(resolver.resolve(ip("fdaa:0:1::2"), &headers), ip("203.0.113.7"));
+}
+
+#[test]
+fn test_x_forwarded_for_skips_trusted_hops_from_the_right() {
+    let resolver = fly_resolver();
+
+    // クライアントが先頭に偽の値を入れても、プロキシが追加した右側の値を使う
+    let spoofed = headers(&[("x-forwarded-for", "198.51.100.1, 203.0.113.7")]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &spoofed), ip("203.0.113.7"));
+
+    let chained = headers(&[
+        ("x-forwarded-for", "198.51.100.1, 203.0.113.7"),
+        ("x-forwarded-for", "172.16.0.9"),
+    ]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &chained), ip("203.0.113.7"));
+
+    // 信頼するプロキシの直前に読めない値があれば接続元を使う
+    let garbage = headers(&[("x-forwarded-for", "203.0.113.7, not-an-ip")]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &garbage), ip("172.16.0.1"));
+
+    let invalid_fly = headers(&[
+        ("fly-client-ip", "not-an-ip"),
+        ("x-forwarded-for", "203.0.113.7"),
+    ]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &invalid_fly), ip("203.0.113.7"));
+}
+
+#[test]
+fn test_forwarded_header() {
+    let resolver = fly_resolver();
+
+    let headers_v6 = headers(&[(
+        "forwarded",
+        r#"for=198.51.100.1;proto=https, For="[2001:db8:cafe::17]:4711";by=172.16.0.1"#,
+    )]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &headers_v6), ip("2001:db8:cafe::17"));
+
+    let with_port = headers(&[("forwarded", "for=203.0.113.7:8080")]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &with_port), ip("203.0.113.7"));
+
+    let obfuscated = headers(&[("forwarded", "for=203.0.113.7, for=unknown")]);
+    assert_eq!(resolver.resolve(ip("172.16.0.1"), &obfuscated), ip("172.16.0.1"));
+}
+
+#[test]
+fn test_ipv6_clients_are_aggregated_by_64_prefix() {
+    assert_eq!(rate_limit_key(ip("2001:db8:1:2:aaaa::1")), ip("2001:db8:1:2::"));
+    assert_eq!(rate_limit_key(ip("203.0.113.7")), ip("203.0.113.7"));
+    assert_eq!(rate_limit_key(ip("::ffff:203.0.113.7")), ip("203.0.113.7"));
+}
+
+#[tokio::test]
+async fn test_limiter_shares_bucket_within_ipv6_64() {
+    let limiter = RateLimiter::new(RateLimitConfig::default());
+    let now = Instant::now();
+
+    assert!(limiter.check_at(ip("2001:db8:1:2::1"), "/", now).await.allowed);
+    assert!(!limiter.check_at(ip("2001:db8:1:2:ffff::9"), "/", now).await.allowed);
+    assert!(limiter.check_at(ip("2001:db8:1:3::1"), "/", now).await.allowed);
+}
@@ -0,0 +1,98 @@
+// 結合テストで共有するヘルパー。テストごとに使うものが違うため未使用の警告は出さない
+#![allow(dead_code)]
+
+use backend::utils::api::{Problem, ProblemModel};
+use backend::utils::routing::{router_with_services, AppState, Services};
+use hyper::http::request::Builder;
+use hyper::{Body, HeaderMap, Method, Request, StatusCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// テストごとの一時ディレクトリのパス。前回の実行で残ったものは消しておく
+pub fn temp_path(prefix: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("backend-{}-{}-{}", prefix, name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// 保存先のファイルのパス。ディレクトリは保存するときに作られる
+pub fn temp_state_file(name: &str) -> PathBuf {
+    temp_path("storage", name).join("state.jsonl")
+}
+
+/// テストごとに空の一時ディレクトリを作る
+pub fn temp_data_dir(prefix: &str, name: &str) -> PathBuf {
+    let dir = temp_path(prefix, name);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// メッセージを英語で確かめるため、Accept-Language: en を付ける
+pub fn request_builder(method: Method, path: &str) -> Builder {
+    Request::builder()
+        .method(method)
+        .uri(path)
+        .header("accept-language", "en")
+}
+
+/// Accept-Language: en を付けた本文のないリクエスト
+pub fn request(method: Method, path: &str) -> Request<Body> {
+    request_builder(method, path).body(Body::empty()).unwrap()
+}
+
+/// ルーターに送り、ステータスとヘッダーと本文を返す
+pub async fn send(
+    req: Request<Body>,
+    state: Arc<AppState>,
+    services: &Services,
+) -> (StatusCode, HeaderMap, String) {
+    let res = router_with_services(req, state, services.clone())
+        .await
+        .unwrap();
+
+    let status = res.status();
+    let headers = res.headers().clone();
+    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    (
+        status,
+        headers,
+        String::from_utf8(body_bytes.to_vec()).unwrap(),
+    )
+}
+
+/// (問題 ID, コンテスト ID, Diff) から問題データを作る。問題名は ID と同じにする
+pub fn build_state(problems: &[(&str, &str, Option<f64>)]) -> AppState {
+    AppState::new(
+        problems
+            .iter()
+            .map(|(id, contest_id, _)| Problem {
+                id: id.to_string(),
+                contest_id: contest_id.to_string(),
+                name: id.to_string(),
+            })
+            .collect(),
+        problems
+            .iter()
+            .map(|(id, _, difficulty)| {
+                (
+                    id.to_string(),
+                    ProblemModel {
+                        difficulty: *difficulty,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+/// abc001 の Diff 800 の問題だけからなる問題データ
+pub fn build_abc001_state(ids: &[&str]) -> Arc<AppState> {
+    let problems = ids
+        .iter()
+        .map(|id| (*id, "abc001", Some(800.0)))
+        .collect::<Vec<_>>();
+    Arc::new(build_state(&problems))
+}
@@ -1,5 +1,11 @@
-use std::collections::HashSet;
 use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use backend::utils::api::DataSource;
+use backend::utils::reload::load_snapshot;
+use backend::utils::routing::router;
+use hyper::{Body, Request, StatusCode};
 
 use serde::Deserialize;
 
@@ -15,6 +21,18 @@ fn standard_contest_number(contest_id: &str, prefix: &str) -> Option<u32> {
         .and_then(|number| number.parse().ok())
 }
 
+#[tokio::test]
+async fn problem_data_passes_strict_validation() {
+    let source = DataSource {
+        dir: PathBuf::from("data"),
+        strict: true,
+    };
+
+    if let Err(e) = load_snapshot(&source).await {
+        panic!("{e}");
+    }
+}
+
 #[test]
 fn abc458_and_abc459_exist_in_problem_data_and_models() {
     let problems_text = fs::read_to_string("data/problems.json").unwrap();
@@ -24,26 +42,6 @@ fn abc458_and_abc459_exist_in_problem_data_and_models() {
     let problem_models: serde_json::Map<String, serde_json::Value> =
         serde_json::from_str(&problem_models_text).unwrap();
 
-    let mut seen = HashSet::new();
-    let mut duplicated = Vec::new();
-    for problem in &problems {
-        if !seen.insert(problem.id.as_str()) {
-            duplicated.push(problem.id.as_str());
-        }
-    }
-    assert!(
-        duplicated.is_empty(),
-        "duplicated problem ids: {duplicated:?}"
-    );
-
-    for problem in &problems {
-        assert!(
-            problem_models.contains_key(&problem.id),
-            "{} is missing from problem-models.json",
-            problem.id
-        );
-    }
-
     for contest in ["abc458", "abc459"] {
         for task in ["a", "b", "c", "d", "e", "f", "g"] {
             let id = format!("{contest}_{task}");
@@ -79,3 +77,23 @@ fn problem_data_is_not_older_than_known_current_standard_contests() {
         );
     }
 }
+
+// 同梱の problem-models.json は difficulty しか含まないため、
+// `node json_fetch.mjs` で取得し直してから `cargo test -- --ignored` で実行する
+#[tokio::test]
+#[ignore = "data/problem-models.json has no discrimination until it is fetched again"]
+async fn problem_data_supports_solve_probability() {
+    let source = DataSource {
+        dir: PathBuf::from("data"),
+        strict: false,
+    };
+    let (state, _) = load_snapshot(&source).await.unwrap();
+    let req = Request::builder()
+        .uri("/?rating=1350&p_min=0.3&p_max=0.6&seed=1")
+        .body(Body::empty())
+        .unwrap();
+
+    let res = router(req, Arc::new(state)).await.unwrap();
+
+    assert_eq!(res.status(), StatusCode::OK);
+}
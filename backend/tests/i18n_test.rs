@@ -0,0 +1,30 @@
+use backend::{Lang, Message};
+
+#[test]
+fn test_lang_from_tag() {
+    assert_eq!(Lang::from_tag("ja"), Some(Lang::Ja));
+    assert_eq!(Lang::from_tag("ja-JP"), Some(Lang::Ja));
+    assert_eq!(Lang::from_tag("EN_us"), Some(Lang::En));
+    assert_eq!(Lang::from_tag("fr"), None);
+    assert_eq!(Lang::from_tag(""), None);
+}
+
+#[test]
+fn test_lang_from_accept_language_uses_quality() {
+    assert_eq!(Lang::from_accept_language("ja,en;q=0.8"), Some(Lang::Ja));
+    assert_eq!(Lang::from_accept_language("ja;q=0.5, en;q=0.8"), Some(Lang::En));
+    assert_eq!(Lang::from_accept_language("fr, ja;q=0.3"), Some(Lang::Ja));
+    assert_eq!(Lang::from_accept_language("en;q=0, ja;q=0.1"), Some(Lang::Ja));
+    assert_eq!(Lang::from_accept_language("fr, *;q=0.5"), None);
+    assert_eq!(Lang::from_accept_language(""), None);
+}
+
+#[test]
+fn test_message_embeds_param() {
+    let message = Message::Between(1, 50);
+    assert_eq!(message.text("count", Lang::En), "'count' must be between 1 and 50.");
+    assert_eq!(
+        message.text("count", Lang::Ja),
+        "'count' には 1 以上 50 以下の値を指定してください"
+    );
+}
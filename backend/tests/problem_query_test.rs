@@ -0,0 +1,158 @@
+mod common;
+
+use backend::utils::routing::AppState;
+use backend::{ApiError, Contest, ErrorCode, Lang, ProblemQuery, QueryError, UnknownDifficulty};
+use common::build_state;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+fn build_test_state() -> AppState {
+    let problems = [
+        ("abc001_a", "abc001", Some(1000.0)),
+        ("arc001_a", "arc001", Some(1100.0)),
+        ("agc001_a", "agc001", Some(1200.0)),
+        ("abc212_b", "adt_all_20260615_2", Some(925.0)),
+        ("typical90_a", "typical90", Some(700.0)),
+        ("abc459_a", "abc459", None),
+    ];
+
+    build_state(&problems)
+}
+
+fn candidate_ids(query: &ProblemQuery, state: &AppState) -> Vec<String> {
+    query
+        .candidates(state)
+        .into_iter()
+        .map(|problem| problem.id.clone())
+        .collect()
+}
+
+#[test]
+fn test_default_query_matches_every_problem_with_model() {
+    let state = build_test_state();
+    let query = ProblemQuery::builder().build().unwrap();
+
+    assert_eq!(candidate_ids(&query, &state).len(), state.problems.len());
+}
+
+#[test]
+fn test_difficulty_range_excludes_unknown_difficulty_by_default() {
+    let state = build_test_state();
+
+    let query = ProblemQuery::builder().min(900.0).max(1100.0).build().unwrap();
+    assert_eq!(
+        candidate_ids(&query, &state),
+        ["abc001_a", "arc001_a", "abc212_b"]
+    );
+
+    let query = ProblemQuery::builder()
+        .min(900.0)
+        .unknown_difficulty(UnknownDifficulty::Include)
+        .build()
+        .unwrap();
+    assert!(candidate_ids(&query, &state).contains(&"abc459_a".to_string()));
+}
+
+#[test]
+fn test_contests_use_the_contest_of_the_problem_id() {
+    let state = build_test_state();
+
+    let query = ProblemQuery::builder()
+        .contests([Contest::ABC, Contest::from_id("typical90")])
+        .build()
+        .unwrap();
+    assert_eq!(
+        candidate_ids(&query, &state),
+        ["abc001_a", "abc212_b", "typical90_a", "abc459_a"]
+    );
+
+    let query = ProblemQuery::builder().contest(Contest::Other).build().unwrap();
+    assert_eq!(candidate_ids(&query, &state), ["typical90_a"]);
+}
+
+#[test]
+fn test_round_range_and_exclusions() {
+    let state = build_test_state();
+    let solved = Arc::new(HashSet::from(["abc212_b".to_string()]));
+
+    let query = ProblemQuery::builder()
+        .round_range(Some(1), Some(300))
+        .exclude(["arc001_a"])
+        .exclude_solved(solved)
+        .build()
+        .unwrap();
+
+    assert_eq!(candidate_ids(&query, &state), ["abc001_a", "agc001_a"]);
+}
+
+#[test]
+fn test_matches_single_problem() {
+    let state = build_test_state();
+    let problem = &state.problems[0];
+    let model = &state.problem_models[&problem.id];
+
+    let query = ProblemQuery::builder().contest(Contest::ABC).max(1000.0).build().unwrap();
+    assert!(query.matches(problem, model));
+
+    let query = ProblemQuery::builder().exclude([problem.id.as_str()]).build().unwrap();
+    assert!(!query.matches(problem, model));
+}
+
+#[test]
+fn test_build_rejects_invalid_ranges() {
+    assert_eq!(
+        ProblemQuery::builder().min(1200.0).max(800.0).build().unwrap_err(),
+        QueryError::MinGreaterThanMax
+    );
+    assert_eq!(
+        ProblemQuery::builder().round_range(Some(300), Some(100)).build().unwrap_err(),
+        QueryError::RoundRangeReversed
+    );
+    let error = ProblemQuery::builder().solve_probability(1500.0, 0.8, 0.2).build().unwrap_err();
+    assert_eq!(error, QueryError::ProbabilityRangeReversed);
+    assert_eq!(error.to_string(), "'p_min' cannot be greater than 'p_max'.");
+}
+
+#[test]
+fn test_query_errors_convert_to_api_errors() {
+    let error = ApiError::from(QueryError::MinGreaterThanMax);
+    assert_eq!(error.code, ErrorCode::MinGreaterThanMax);
+    assert_eq!(error.param.as_deref(), Some("min"));
+    assert_eq!(
+        error.localized_message(Lang::En),
+        "'min' cannot be greater than 'max'."
+    );
+    assert_eq!(
+        error.localized_message(Lang::Ja),
+        "'min' は 'max' 以下にしてください"
+    );
+
+    let error = ApiError::from(QueryError::RoundRangeReversed);
+    assert_eq!(error.code, ErrorCode::MinGreaterThanMax);
+    assert_eq!(error.param.as_deref(), Some("contest_from"));
+
+    let error = ApiError::from(QueryError::ProbabilityMaxOutOfRange);
+    assert_eq!(error.code, ErrorCode::OutOfRange);
+    assert_eq!(error.param.as_deref(), Some("p_max"));
+}
+
+#[test]
+fn test_to_builder_keeps_other_conditions() {
+    let state = build_test_state();
+    let query = ProblemQuery::builder()
+        .contest(Contest::ABC)
+        .min(900.0)
+        .exclude(["abc001_a"])
+        .build()
+        .unwrap();
+
+    let rebuilt = query.to_builder().max(950.0).build().unwrap();
+    assert_eq!(candidate_ids(&rebuilt, &state), ["abc212_b"]);
+
+    let rebuilt = query
+        .to_builder()
+        .unknown_difficulty(UnknownDifficulty::Include)
+        .build()
+        .unwrap();
+    assert_eq!(candidate_ids(&rebuilt, &state), ["abc212_b", "abc459_a"]);
+}
@@ -0,0 +1,168 @@
+use backend::utils::i18n::Lang;
+use backend::utils::ratelimiter::{RateLimit, RateLimitConfig, RateLimiter};
+use hyper::StatusCode;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+fn ip(last: u8) -> IpAddr {
+    IpAddr::from([192, 0, 2, last])
+}
+
+#[tokio::test]
+async fn test_default_preset_allows_one_request_per_second() {
+    let limiter = RateLimiter::new(RateLimitConfig::default());
+    let start = Instant::now();
+
+    assert!(limiter.check_at(ip(1), "/", start).await.allowed);
+    assert!(!limiter.check_at(ip(1), "/", start + Duration::from_millis(999)).await.allowed);
+    // 別の IP には影響しない
+    assert!(limiter.check_at(ip(2), "/", start + Duration::from_millis(999)).await.allowed);
+    assert!(limiter.check_at(ip(1), "/", start + Duration::from_secs(1)).await.allowed);
+    assert!(!limiter.check_at(ip(1), "/", start + Duration::from_millis(1500)).await.allowed);
+}
+
+#[tokio::test]
+async fn test_bucket_allows_burst_and_refills() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        default: RateLimit::new(3.0, 2.0).unwrap(),
+        routes: Vec::new(),
+    });
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        assert!(limiter.check_at(ip(1), "/", start).await.allowed);
+    }
+    assert!(!limiter.check_at(ip(1), "/", start).await.allowed);
+
+    // 0.5 秒で 1 トークン回復する
+    let later = start + Duration::from_millis(500);
+    assert!(limiter.check_at(ip(1), "/", later).await.allowed);
+    assert!(!limiter.check_at(ip(1), "/", later).await.allowed);
+
+    // 長く空いても容量以上には貯まらない
+    let much_later = start + Duration::from_secs(60);
+    for _ in 0..3 {
+        assert!(limiter.check_at(ip(1), "/", much_later).await.allowed);
+    }
+    assert!(!limiter.check_at(ip(1), "/", much_later).await.allowed);
+}
+
+#[tokio::test]
+async fn test_routes_have_their_own_limits() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        default: RateLimit::ONE_PER_SECOND,
+        routes: RateLimitConfig::parse_routes("/stats=5:5; /problems=2:1").unwrap(),
+    });
+    let start = Instant::now();
+
+    for _ in 0..5 {
+        assert!(limiter.check_at(ip(1), "/stats", start).await.allowed);
+    }
+    assert!(!limiter.check_at(ip(1), "/stats", start).await.allowed);
+
+    // ルートごとにバケットが分かれる
+    assert!(limiter.check_at(ip(1), "/pick", start).await.allowed);
+    assert!(!limiter.check_at(ip(1), "/pick", start).await.allowed);
+
+    assert!(limiter.check_at(ip(1), "/problems", start).await.allowed);
+    assert!(limiter.check_at(ip(1), "/problems/abc001_a", start).await.allowed);
+    assert!(!limiter.check_at(ip(1), "/problems/abc001_a", start).await.allowed);
+    // パスの区切りが違えば既定の制限になり、`/pick` とバケットを共有する
+    assert!(!limiter.check_at(ip(1), "/problemset", start).await.allowed);
+}
+
+#[test]
+fn test_parse_rate_limit() {
+    assert_eq!(RateLimit::parse("1rps"), Ok(RateLimit::ONE_PER_SECOND));
+    assert_eq!(
+        RateLimit::parse(" 10 : 0.5 "),
+        Ok(RateLimit {
+            capacity: 10.0,
+            refill_per_sec: 0.5,
+        })
+    );
+    assert!(RateLimit::parse("10").is_err());
+    assert!(RateLimit::parse("0:1").is_err());
+    assert!(RateLimit::parse("10:0").is_err());
+    assert!(RateLimit::parse("ten:1").is_err());
+    assert!(RateLimit::parse("1e308:1e-308").is_err());
+
+    assert!(RateLimitConfig::parse_routes("").unwrap().is_empty());
+    assert!(RateLimitConfig::parse_routes("stats=1:1").is_err());
+    assert!(RateLimitConfig::parse_routes("/stats").is_err());
+}
+
+#[tokio::test]
+async fn test_status_reports_remaining_and_reset() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        default: RateLimit::new(3.0, 0.5).unwrap(),
+        routes: Vec::new(),
+    });
+    let start = Instant::now();
+
+    let status = limiter.check_at(ip(1), "/", start).await;
+    assert!(status.allowed);
+    assert_eq!(status.limit, 3);
+    assert_eq!(status.remaining, 2);
+    assert_eq!(status.reset, Duration::from_secs(2));
+    assert_eq!(status.retry_after, None);
+
+    limiter.check_at(ip(1), "/", start).await;
+    limiter.check_at(ip(1), "/", start).await;
+    let status = limiter.check_at(ip(1), "/", start + Duration::from_secs(1)).await;
+    assert!(!status.allowed);
+    assert_eq!(status.remaining, 0);
+    assert_eq!(status.retry_after, Some(Duration::from_secs(1)));
+    assert_eq!(status.reset, Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_rejected_response_has_rate_limit_and_cors_headers() {
+    let limiter = RateLimiter::new(RateLimitConfig::default());
+    let start = Instant::now();
+    limiter.check_at(ip(1), "/", start).await;
+    let status = limiter.check_at(ip(1), "/", start + Duration::from_millis(300)).await;
+
+    let res = status.rejected_response(Lang::En);
+
+    assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    let headers = res.headers();
+    assert_eq!(headers["ratelimit-limit"], "1");
+    assert_eq!(headers["ratelimit-remaining"], "0");
+    assert_eq!(headers["ratelimit-reset"], "1");
+    assert_eq!(headers["retry-after"], "1");
+    assert_eq!(headers["access-control-allow-origin"], "*");
+    assert!(headers["access-control-expose-headers"]
+        .to_str()
+        .unwrap()
+        .contains("Retry-After"));
+
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    let err: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(err["code"], "rate_limited");
+}
+
+#[tokio::test]
+async fn test_evict_idle_removes_only_refilled_buckets() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        default: RateLimit::ONE_PER_SECOND,
+        routes: RateLimitConfig::parse_routes("/stats=10:1").unwrap(),
+    });
+    let start = Instant::now();
+
+    for last in 0..100 {
+        limiter.check_at(ip(last), "/", start).await;
+    }
+    limiter.check_at(ip(1), "/stats", start).await;
+    assert_eq!(limiter.tracked(), 101);
+
+    // 既定のバケットは 1 秒、/stats は 10 秒で満杯に戻る
+    assert_eq!(limiter.evict_idle(start + Duration::from_millis(500)), 0);
+    assert_eq!(limiter.evict_idle(start + Duration::from_secs(1)), 100);
+    assert_eq!(limiter.tracked(), 1);
+    assert_eq!(limiter.evict_idle(start + Duration::from_secs(10)), 1);
+    assert_eq!(limiter.tracked(), 0);
+
+    // 捨てたあとも新しいバケットとして同じ判定になる
+    assert!(limiter.check_at(ip(1), "/", start + Duration::from_secs(10)).await.allowed);
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use backend::utils::refresh::{refresh_data, RefreshOptions, DEFAULT_SOURCE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server, StatusCode};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use common::temp_path;
+
+/// 指定したパスに固定の JSON を返すローカルサーバーを起動する
+fn spawn_upstream(files: HashMap<&'static str, &'static str>) -> String {
+    let files = Arc::new(files);
+    let make_svc = make_service_fn(move |_| {
+        let files = files.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let body = files.get(req.uri().path()).copied();
+                async move {
+                    let res = match body {
+                        Some(body) => Response::new(Body::from(body)),
+                        None => {
+                            let mut res = Response::new(Body::empty());
+                            *res.status_mut() = StatusCode::NOT_FOUND;
+                            res
+                        }
+                    };
+                    Ok::<_, Infallible>(res)
+                }
+            }))
+        }
+    });
+
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    format!("http://{addr}/resources/")
+}
+
+#[test]
+fn test_refresh_options_from_args() {
+    let args = ["--source", "http://localhost:8080/", "--out", "tmp"]
+        .map(String::from)
+        .to_vec();
+    let options = RefreshOptions::from_args(&args).unwrap();
+
+    assert_eq!(options.source, "http://localhost:8080");
+    assert_eq!(options.out, PathBuf::from("tmp"));
+
+    let options = RefreshOptions::from_args(&[]).unwrap();
+    assert_eq!(options.source, DEFAULT_SOURCE);
+
+    let err = RefreshOptions::from_args(&["--source".to_string()]).unwrap_err();
+    assert_eq!(err, "'--source' requires a value.");
+}
+
+#[tokio::test]
+async fn test_refresh_writes_validated_data_and_reports_diff() {
+    let source = spawn_upstream(HashMap::from([
+        (
+            "/resources/problems.json",
+            r#"[
+                {"id":"abc001_a","contest_id":"abc001","problem_index":"A","name":"A","title":"A. A"},
+                {"id":"abc001_c","contest_id":"abc001","problem_index":"C","name":"C","title":"C. C"}
+            ]"#,
+        ),
+        (
+            "/resources/problem-models.json",
+            r#"{"abc001_a":{"difficulty":-100,"slope":-0.001},"abc999_z":{"difficulty":3000}}"#,
+        ),
+    ]));
+
+    let out = temp_path("refresh", "success");
+    fs::create_dir_all(&out).unwrap();
+    fs::write(
+        out.join("problems.json"),
+        r#"[{"id":"abc001_a","contest_id":"abc001","name":"A"},{"id":"abc001_b","contest_id":"abc001","name":"B"}]"#,
+    )
+    .unwrap();
+
+    let options = RefreshOptions::from_args(&[
+        "--source".to_string(),
+        source,
+        "--out".to_string(),
+        out.display().to_string(),
+    ])
+    .unwrap();
+    let summary = refresh_data(&options).await.unwrap();
+
+    assert_eq!(summary.previous_count, 2);
+    assert_eq!(summary.current_count, 2);
+    assert_eq!(summary.added, ["abc001_c"]);
+    assert_eq!(summary.removed, ["abc001_b"]);
+    assert_eq!(summary.problems_without_models, 1);
+    assert_eq!(summary.models_without_problems, 1);
+
+    let problems: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out.join("problems.json")).unwrap()).unwrap();
+    assert_eq!(problems[1]["id"], "abc001_c");
+    assert!(problems[1].get("title").is_none());
+
+    let models: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out.join("problem-models.json")).unwrap())
+            .unwrap();
+    assert_eq!(models["abc001_a"]["difficulty"], -100);
+    assert_eq!(models["abc001_a"]["slope"], -0.001);
+    assert!(models["abc001_c"]["difficulty"].is_null());
+    assert!(models.get("abc999_z").is_none());
+
+    fs::remove_dir_all(out).unwrap();
+}
+
+#[tokio::test]
+async fn test_refresh_keeps_old_files_when_upstream_is_invalid() {
+    let source = spawn_upstream(HashMap::from([
+        (
+            "/resources/problems.json",
+            r#"[{"id":"abc001_a","contest_id":"abc001","name":"A"},{"id":"abc001_a","contest_id":"abc001","name":"A"}]"#,
+        ),
+        ("/resources/problem-models.json", r#"{}"#),
+    ]));
+
+    let out = temp_path("refresh", "invalid");
+    fs::create_dir_all(&out).unwrap();
+    fs::write(out.join("problems.json"), "[]").unwrap();
+
+    let options = RefreshOptions {
+        source: source.trim_end_matches('/').to_string(),
+        out: out.clone(),
+    };
+    let err = refresh_data(&options).await.unwrap_err();
+
+    assert!(err.to_string().contains("duplicated problem ids (1): abc001_a"));
+    assert_eq!(fs::read_to_string(out.join("problems.json")).unwrap(), "[]");
+    assert!(!out.join("problem-models.json").exists());
+
+    fs::remove_dir_all(out).unwrap();
+}
+
+#[tokio::test]
+async fn test_refresh_rejects_models_that_match_no_problem() {
+    let source = spawn_upstream(HashMap::from([
+        (
+            "/resources/problems.json",
+            r#"[{"id":"abc001_a","contest_id":"abc001","name":"A"}]"#,
+        ),
+        ("/resources/problem-models.json", r#"{"abc999_z":{"difficulty":3000}}"#),
+    ]));
+
+    let out = temp_path("refresh", "unmatched");
+    let options = RefreshOptions {
+        source: source.trim_end_matches('/').to_string(),
+        out: out.clone(),
+    };
+    let err = refresh_data(&options).await.unwrap_err();
+
+    assert!(err.to_string().contains("problems without models (1): abc001_a"));
+    assert!(!out.exists());
+}
+
+#[tokio::test]
+async fn test_refresh_fails_on_upstream_error_status() {
+    let source = spawn_upstream(HashMap::new());
+    let options = RefreshOptions {
+        source: source.trim_end_matches('/').to_string(),
+        out: temp_path("refresh", "status"),
+    };
+
+    let err = refresh_data(&options).await.unwrap_err();
+
+    assert!(err.to_string().contains("404"));
+    assert!(!options.out.exists());
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use backend::utils::api::DataSource;
+use backend::utils::reload::{DataReloader, SharedState, SnapshotInfo};
+use backend::utils::routing::AppState;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use common::temp_data_dir;
+
+fn write_data(dir: &Path, ids: &[&str]) {
+    let problems = ids
+        .iter()
+        .map(|id| format!(r#"{{"id":"{id}","contest_id":"abc001","name":"{id}"}}"#))
+        .collect::<Vec<_>>()
+        .join(",");
+    let models = ids
+        .iter()
+        .map(|id| format!(r#""{id}":{{"difficulty":800}}"#))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    fs::write(dir.join("problems.json"), format!("[{problems}]")).unwrap();
+    fs::write(dir.join("problem-models.json"), format!("{{{models}}}")).unwrap();
+}
+
+fn source(dir: &Path) -> DataSource {
+    DataSource {
+        dir: dir.to_path_buf(),
+        strict: true,
+    }
+}
+
+fn empty_state() -> SharedState {
+    SharedState::new(AppState::new(Vec::new(), HashMap::new()))
+}
+
+#[tokio::test]
+async fn test_reloader_swaps_state_when_files_change() {
+    let dir = temp_data_dir("reload", "swap");
+    write_data(&dir, &["abc001_a"]);
+
+    let state = empty_state();
+    let mut reloader = DataReloader::new(source(&dir), state.clone());
+    assert!(!reloader.check().await);
+
+    write_data(&dir, &["abc001_a", "abc001_b"]);
+    assert!(reloader.check().await);
+    assert_eq!(state.load().problems.len(), 2);
+    assert!(!reloader.check().await);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_reloader_keeps_old_state_when_new_files_are_invalid() {
+    let dir = temp_data_dir("reload", "invalid");
+    write_data(&dir, &["abc001_a"]);
+
+    let state = empty_state();
+    let mut reloader = DataReloader::new(source(&dir), state.clone());
+    write_data(&dir, &["abc001_a", "abc001_b"]);
+    assert!(reloader.check().await);
+
+    fs::write(dir.join("problems.json"), "[{\"id\":").unwrap();
+    assert!(!reloader.check().await);
+    assert_eq!(state.load().problems.len(), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_loaded_snapshot_is_not_affected_by_replace() {
+    let state = empty_state();
+    let snapshot = state.load();
+
+    let replacement = AppState::new(
+        Vec::new(),
+        HashMap::from([(
+            "abc001_a".to_string(),
+            backend::utils::api::ProblemModel {
+                difficulty: Some(400.0),
+                ..Default::default()
+            },
+        )]),
+    );
+    let info = SnapshotInfo::from_state(&replacement);
+    state.replace(replacement, info);
+
+    assert!(snapshot.problem_models.is_empty());
+    assert_eq!(state.load().problem_models.len(), 1);
+}
+
+#[tokio::test]
+async fn test_strict_reloader_keeps_old_state_when_models_are_missing() {
+    let dir = temp_data_dir("reload", "strict");
+    write_data(&dir, &["abc001_a"]);
+
+    let state = empty_state();
+    let mut reloader = DataReloader::new(source(&dir), state.clone());
+    write_data(&dir, &["abc001_a", "abc001_b"]);
+    assert!(reloader.check().await);
+
+    fs::write(
+        dir.join("problem-models.json"),
+        r#"{"abc001_a":{"difficulty":800}}"#,
+    )
+    .unwrap();
+    assert!(!reloader.check().await);
+    assert_eq!(state.load().problems.len(), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -13,51 +13,79 @@ fn build_test_state() -> Arc<AppState> {
         "abc001_a".to_string(),
         ProblemModel {
             difficulty: Some(1000.0),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "arc001_a".to_string(),
         ProblemModel {
             difficulty: Some(1100.0),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "agc001_a".to_string(),
         ProblemModel {
             difficulty: Some(1200.0),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "abc212_a".to_string(),
         ProblemModel {
             difficulty: Some(900.0),
+            slope: Some(-0.0005),
+            intercept: Some(7.5),
+            variance: Some(0.2),
+            discrimination: Some(0.004),
+            is_experimental: Some(false),
+            irt_loglikelihood: Some(-120.5),
+        },
+    );
+    problem_models.insert(
+        "abc460_a".to_string(),
+        ProblemModel {
+            difficulty: Some(1300.0),
+            is_experimental: Some(true),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "abc212_b".to_string(),
         ProblemModel {
             difficulty: Some(925.0),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "abc213_a".to_string(),
         ProblemModel {
             difficulty: Some(850.0),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "arc212_a".to_string(),
         ProblemModel {
             difficulty: Some(950.0),
+            discrimination: Some(0.004),
+            ..Default::default()
         },
     );
     problem_models.insert(
         "typical90_a".to_string(),
         ProblemModel {
             difficulty: Some(700.0),
+            ..Default::default()
+        },
+    );
+    problem_models.insert(
+        "abc459_a".to_string(),
+        ProblemModel {
+            difficulty: None,
+            ..Default::default()
         },
     );
-    problem_models.insert("abc459_a".to_string(), ProblemModel { difficulty: None });
 
     let problems = vec![
         Problem {
@@ -105,18 +133,29 @@ fn build_test_state() -> Arc<AppState> {
             contest_id: "abc459".to_string(),
             name: "A - Hell, World!".to_string(),
         },
+        Problem {
+            id: "abc460_a".to_string(),
+            contest_id: "abc460".to_string(),
+            name: "A - Experimental".to_string(),
+        },
     ];
 
-    Arc::new(AppState {
-        problems,
-        problem_models,
-    })
+    Arc::new(AppState::new(problems, problem_models))
+}
+
+fn assert_error(body: &str, code: &str, param: Option<&str>, message: &str) {
+    let error: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(error["code"], code);
+    assert_eq!(error["param"].as_str(), param);
+    assert_eq!(error["message"], message);
 }
 
 async fn build_and_send(method: Method, path: &str) -> (StatusCode, String) {
+    // メッセージは英語で確かめる
     let req = Request::builder()
         .method(method)
         .uri(path)
+        .header("accept-language", "en")
         .body(Body::empty())
         .unwrap();
 
@@ -134,21 +173,19 @@ async fn build_and_send(method: Method, path: &str) -> (StatusCode, String) {
 async fn test_not_found_path() {
     let (status, body) = build_and_send(Method::GET, "/test").await;
     assert_eq!(status, StatusCode::NOT_FOUND);
-    assert_eq!(body, "404 Not Found");
+    assert_error(&body, "not_found", None, "404 Not Found");
 }
 
 #[tokio::test]
 async fn test_not_found_problem() {
     let (status, body) = build_and_send(Method::GET, "/?min=0&max=500").await;
     assert_eq!(status, StatusCode::NOT_FOUND);
-
-    #[derive(serde::Deserialize)]
-    struct ErrorResponse {
-        message: String,
-    }
-
-    let err: ErrorResponse = serde_json::from_str(&body).unwrap();
-    assert_eq!(err.message, "指定Diff範囲に該当する問題がありませんでした");
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
 }
 
 #[tokio::test]
@@ -177,16 +214,11 @@ async fn test_all_matching_problems_excluded_returns_specific_message() {
     )
     .await;
     assert_eq!(status, StatusCode::NOT_FOUND);
-
-    #[derive(serde::Deserialize)]
-    struct ErrorResponse {
-        message: String,
-    }
-
-    let error: ErrorResponse = serde_json::from_str(&body).unwrap();
-    assert_eq!(
-        error.message,
-        "履歴内の問題を除外すると、条件に一致する問題がありません。除外をOFFにするか、履歴を削除してください"
+    assert_error(
+        &body,
+        "excluded_all",
+        None,
+        "No problems match the conditions after excluding your history. Turn off exclusion or clear your history.",
     );
 }
 
@@ -198,8 +230,7 @@ async fn test_empty_exclude_is_ignored() {
 
 #[tokio::test]
 async fn test_duplicate_excluded_problem_ids_count_once() {
-    let excluded = std::iter::repeat("abc212_a")
-        .take(21)
+    let excluded = std::iter::repeat_n("abc212_a", 21)
         .collect::<Vec<_>>()
         .join(",");
     let path = format!("/?min=850&max=850&exclude={excluded}");
@@ -225,7 +256,7 @@ async fn test_more_than_twenty_excluded_problem_ids_is_bad_request() {
     let (status, body) = build_and_send(Method::GET, &path).await;
 
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'exclude' cannot contain more than 20 problem IDs.");
+    assert_error(&body, "too_many_values", Some("exclude"), "'exclude' cannot contain more than 20 problem IDs.");
 }
 
 #[tokio::test]
@@ -233,21 +264,21 @@ async fn test_invalid_excluded_problem_id_is_bad_request() {
     let (status, body) = build_and_send(Method::GET, "/?exclude=abc212%2Fa").await;
 
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'exclude' contains an invalid problem ID.");
+    assert_error(&body, "invalid_exclude_id", Some("exclude"), "'exclude' contains an invalid problem ID.");
 }
 
 #[tokio::test]
 async fn test_min_greater_than_max() {
     let (status, body) = build_and_send(Method::GET, "/?min=1500&max=500").await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'min' cannot be greater than 'max'.");
+    assert_error(&body, "min_greater_than_max", Some("min"), "'min' cannot be greater than 'max'.");
 }
 
 #[tokio::test]
 async fn test_negative_min_is_bad_request() {
     let (status, body) = build_and_send(Method::GET, "/?min=-1&max=500").await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'min' cannot be less than 0.");
+    assert_error(&body, "out_of_range", Some("min"), "'min' cannot be less than 0.");
 }
 
 #[tokio::test]
@@ -269,19 +300,35 @@ async fn test_max_over_previous_upper_limit_is_allowed() {
 async fn test_invalid_min_is_bad_request() {
     let (status, body) = build_and_send(Method::GET, "/?min=abc&max=500").await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'min' must be a number.");
+    assert_error(&body, "invalid_number", Some("min"), "'min' must be a number.");
 }
 
 #[tokio::test]
 async fn test_invalid_contest_from_is_bad_request() {
     let (status, body) = build_and_send(Method::GET, "/?contest_from=abc").await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'contest_from' must be a positive integer.");
+    assert_error(&body, "invalid_integer", Some("contest_from"), "'contest_from' must be a positive integer.");
 }
 
 #[tokio::test]
+async fn test_too_many_contests_are_rejected() {
+    let contests = (1..=11).map(|i| format!("c{}", i)).collect::<Vec<_>>().join(",");
+    let (status, body) = build_and_send(Method::GET, &format!("/?contest={}", contests)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "too_many_values", Some("contest"), "'contest' cannot contain more than 10 contests.");
+}
+
+#[tokio::test]
+async fn test_long_contest_is_rejected() {
+    let (status, body) = build_and_send(Method::GET, &format!("/?contest={}", "a".repeat(33))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_value", Some("contest"), "Each contest in 'contest' must be at most 32 characters.");
+}
+
+#[tokio::test]
+#[allow(clippy::manual_range_contains)]
 async fn test_random_range() {
-    let (status, body) = build_and_send(Method::GET, "/?min=500&max=1500").await;
+    let (status, body) = build_and_send(Method::GET, "/?min=500&max=1500&seed=42").await;
     assert_eq!(status, StatusCode::OK);
 
     #[derive(serde::Deserialize)]
@@ -416,14 +463,12 @@ async fn test_agc_only_with_large_max_is_not_bad_request() {
 async fn test_agc_only_without_matching_difficulty_is_not_found() {
     let (status, body) = build_and_send(Method::GET, "/?min=1300&max=1400&contest=agc").await;
     assert_eq!(status, StatusCode::NOT_FOUND);
-
-    #[derive(serde::Deserialize)]
-    struct ErrorResponse {
-        message: String,
-    }
-
-    let err: ErrorResponse = serde_json::from_str(&body).unwrap();
-    assert_eq!(err.message, "指定Diff範囲に該当する問題がありませんでした");
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
 }
 
 #[tokio::test]
@@ -516,21 +561,19 @@ async fn test_contest_number_filter_excludes_other_contests() {
     )
     .await;
     assert_eq!(status, StatusCode::NOT_FOUND);
-
-    #[derive(serde::Deserialize)]
-    struct ErrorResponse {
-        message: String,
-    }
-
-    let err: ErrorResponse = serde_json::from_str(&body).unwrap();
-    assert_eq!(err.message, "指定Diff範囲に該当する問題がありませんでした");
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
 }
 
 #[tokio::test]
 async fn test_contest_from_greater_than_to() {
     let (status, body) = build_and_send(Method::GET, "/?contest_from=300&contest_to=200").await;
     assert_eq!(status, StatusCode::BAD_REQUEST);
-    assert_eq!(body, "'contest_from' cannot be greater than 'contest_to'.");
+    assert_error(&body, "min_greater_than_max", Some("contest_from"), "'contest_from' cannot be greater than 'contest_to'.");
 }
 
 #[tokio::test]
@@ -561,6 +604,78 @@ async fn test_full_difficulty_range_excludes_problem_without_difficulty() {
     )
     .await;
     assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
+}
+
+#[tokio::test]
+async fn test_explicit_difficulty_range_excludes_problem_without_difficulty() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/?min=0&max=1500&contest=abc&contest_from=459&contest_to=459",
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
+}
+
+#[tokio::test]
+async fn test_count_returns_distinct_problems() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/?min=800&max=1000&contest=abc,arc&contest_from=212&contest_to=213&count=4",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+    }
+
+    let problems: Vec<ProblemResponse> = serde_json::from_str(&body).unwrap();
+    let mut ids = problems
+        .iter()
+        .map(|problem| problem.id.as_str())
+        .collect::<Vec<_>>();
+    ids.sort();
+
+    assert_eq!(ids, ["abc212_a", "abc212_b", "abc213_a", "arc212_a"]);
+}
+
+#[tokio::test]
+async fn test_count_respects_exclusions() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/?min=800&max=900&contest=abc&contest_from=212&contest_to=213&exclude=abc212_a&count=1",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+    }
+
+    let problems: Vec<ProblemResponse> = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].id, "abc213_a");
+}
+
+#[tokio::test]
+async fn test_count_greater_than_candidates_is_not_found() {
+    let (status, body) = build_and_send(Method::GET, "/?min=800&max=900&count=3").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
 
     #[derive(serde::Deserialize)]
     struct ErrorResponse {
@@ -568,16 +683,256 @@ async fn test_full_difficulty_range_excludes_problem_without_difficulty() {
     }
 
     let err: ErrorResponse = serde_json::from_str(&body).unwrap();
-    assert_eq!(err.message, "指定Diff範囲に該当する問題がありませんでした");
+    assert_eq!(
+        err.message,
+        "Only 2 problems match the conditions (3 requested)."
+    );
 }
 
 #[tokio::test]
-async fn test_explicit_difficulty_range_excludes_problem_without_difficulty() {
+async fn test_count_out_of_range_is_bad_request() {
+    for count in ["0", "51"] {
+        let (status, body) = build_and_send(Method::GET, &format!("/?count={count}")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_error(&body, "out_of_range", Some("count"), "'count' must be between 1 and 50.");
+    }
+}
+
+#[tokio::test]
+async fn test_same_seed_picks_same_problem() {
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+        seed: u64,
+    }
+
+    let path = "/?min=500&max=1500&seed=12345";
+    let (status, first) = build_and_send(Method::GET, path).await;
+    assert_eq!(status, StatusCode::OK);
+    let first: ProblemResponse = serde_json::from_str(&first).unwrap();
+    assert_eq!(first.seed, 12345);
+
+    for _ in 0..10 {
+        let (_, body) = build_and_send(Method::GET, path).await;
+        let problem: ProblemResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(problem.id, first.id);
+    }
+}
+
+#[tokio::test]
+async fn test_seed_picks_the_same_problems_across_releases() {
+    // 共有された seed で同じ問題を選べるよう、乱数のアルゴリズムは固定している
+    let (status, body) = build_and_send(Method::GET, "/?min=500&max=1500&count=3&seed=12345").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let problems: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let ids = problems
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|problem| problem["id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["abc212_b", "abc001_a", "typical90_a"]);
+}
+
+#[tokio::test]
+async fn test_same_seed_picks_same_problem_set() {
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+        seed: u64,
+    }
+
+    let path = "/?min=500&max=1500&count=3&seed=7";
+    let (status, first) = build_and_send(Method::GET, path).await;
+    assert_eq!(status, StatusCode::OK);
+    let first: Vec<ProblemResponse> = serde_json::from_str(&first).unwrap();
+    assert!(first.iter().all(|problem| problem.seed == 7));
+
+    for _ in 0..10 {
+        let (_, body) = build_and_send(Method::GET, path).await;
+        let problems: Vec<ProblemResponse> = serde_json::from_str(&body).unwrap();
+        assert_eq!(
+            problems.iter().map(|problem| &problem.id).collect::<Vec<_>>(),
+            first.iter().map(|problem| &problem.id).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_seed_is_echoed_when_omitted() {
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+        seed: u64,
+    }
+
+    let (status, body) = build_and_send(Method::GET, "/?min=500&max=1500").await;
+    assert_eq!(status, StatusCode::OK);
+    let first: ProblemResponse = serde_json::from_str(&body).unwrap();
+
+    let path = format!("/?min=500&max=1500&seed={}", first.seed);
+    let (_, body) = build_and_send(Method::GET, &path).await;
+    let replayed: ProblemResponse = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(replayed.id, first.id);
+}
+
+#[tokio::test]
+async fn test_invalid_seed_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/?seed=-1").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_integer", Some("seed"), "'seed' must be a non-negative integer.");
+
+    let (status, body) = build_and_send(Method::GET, "/?seed=9007199254740992").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "out_of_range", Some("seed"), "'seed' must be between 0 and 9007199254740991.");
+}
+
+#[tokio::test]
+async fn test_daily_problem_is_stable_for_the_same_date() {
+    #[derive(serde::Deserialize)]
+    struct DailyProblemResponse {
+        id: String,
+        date: String,
+    }
+
+    let path = "/daily?min=500&max=1500&date=2026-01-02";
+    let (status, body) = build_and_send(Method::GET, path).await;
+    assert_eq!(status, StatusCode::OK);
+    let first: DailyProblemResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(first.date, "2026-01-02");
+
+    for _ in 0..10 {
+        let (_, body) = build_and_send(Method::GET, path).await;
+        let daily: DailyProblemResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(daily.id, first.id);
+    }
+}
+
+#[tokio::test]
+async fn test_daily_problem_changes_across_dates() {
+    #[derive(serde::Deserialize)]
+    struct DailyProblemResponse {
+        id: String,
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    for day in 1..=28 {
+        let path = format!("/daily?min=500&max=1500&date=2026-02-{day:02}");
+        let (status, body) = build_and_send(Method::GET, &path).await;
+        assert_eq!(status, StatusCode::OK);
+        let daily: DailyProblemResponse = serde_json::from_str(&body).unwrap();
+        ids.insert(daily.id);
+    }
+
+    assert!(ids.len() > 1);
+}
+
+#[tokio::test]
+async fn test_daily_problem_respects_filters() {
+    #[derive(serde::Deserialize)]
+    struct DailyProblemResponse {
+        id: String,
+    }
+
     let (status, body) = build_and_send(
         Method::GET,
-        "/?min=0&max=1500&contest=abc&contest_from=459&contest_to=459",
+        "/daily?min=950&max=975&contest=abc,arc&contest_from=212&contest_to=212",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let daily: DailyProblemResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(daily.id, "arc212_a");
+}
+
+#[tokio::test]
+async fn test_daily_problem_accepts_utc_offset() {
+    #[derive(serde::Deserialize)]
+    struct DailyProblemResponse {
+        date: String,
+    }
+
+    for tz in ["UTC", "JST", "%2B09:00", "-05:00"] {
+        let (status, body) = build_and_send(Method::GET, &format!("/daily?tz={tz}")).await;
+        assert_eq!(status, StatusCode::OK);
+        let daily: DailyProblemResponse = serde_json::from_str(&body).unwrap();
+        assert!(chrono::NaiveDate::parse_from_str(&daily.date, "%Y-%m-%d").is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_daily_problem_with_invalid_date_or_tz_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/daily?date=2026-13-01").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_value", Some("date"), "'date' must be in YYYY-MM-DD format.");
+
+    let (status, body) = build_and_send(Method::GET, "/daily?tz=Asia/Tokyo").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_value", Some("tz"), "'tz' must be a UTC offset such as +09:00.");
+}
+
+#[tokio::test]
+async fn test_contest_set_picks_one_problem_per_band() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/contest-set?bands=0-800,800-900,900-1000,1000-1200&seed=1",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+        difficulty: f64,
+    }
+
+    let problems: Vec<ProblemResponse> = serde_json::from_str(&body).unwrap();
+    assert_eq!(problems.len(), 4);
+
+    for (problem, (min, max)) in problems
+        .iter()
+        .zip([(0.0, 800.0), (800.0, 900.0), (900.0, 1000.0), (1000.0, 1200.0)])
+    {
+        assert!((min..=max).contains(&problem.difficulty));
+    }
+
+    let ids = problems
+        .iter()
+        .map(|problem| problem.id.as_str())
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(ids.len(), 4);
+}
+
+#[tokio::test]
+async fn test_contest_set_does_not_repeat_problems_across_overlapping_bands() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/contest-set?bands=900-925,900-925&contest=abc",
     )
     .await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+    }
+
+    let problems: Vec<ProblemResponse> = serde_json::from_str(&body).unwrap();
+    let mut ids = problems
+        .iter()
+        .map(|problem| problem.id.as_str())
+        .collect::<Vec<_>>();
+    ids.sort();
+
+    assert_eq!(ids, ["abc212_a", "abc212_b"]);
+}
+
+#[tokio::test]
+async fn test_contest_set_names_band_without_candidates() {
+    let (status, body) =
+        build_and_send(Method::GET, "/contest-set?bands=0-800,1400-1600").await;
     assert_eq!(status, StatusCode::NOT_FOUND);
 
     #[derive(serde::Deserialize)]
@@ -586,5 +941,617 @@ async fn test_explicit_difficulty_range_excludes_problem_without_difficulty() {
     }
 
     let err: ErrorResponse = serde_json::from_str(&body).unwrap();
-    assert_eq!(err.message, "指定Diff範囲に該当する問題がありませんでした");
+    assert_eq!(err.message, "No problems match the difficulty range 1400-1600.");
+}
+
+#[tokio::test]
+async fn test_contest_set_with_invalid_bands_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/contest-set").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "missing_parameter", Some("bands"), "'bands' is required.");
+
+    for bands in ["400", "800-400", "a-b"] {
+        let (status, body) =
+            build_and_send(Method::GET, &format!("/contest-set?bands={bands}")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_error(
+            &body,
+            "invalid_value",
+            Some("bands"),
+            "'bands' must be a comma-separated list of ranges such as 0-400,400-800.",
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_model_fields_are_omitted_by_default() {
+    let (status, body) = build_and_send(Method::GET, "/?min=900&max=900&contest=abc").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem["id"], "abc212_a");
+    assert!(problem.get("model").is_none());
+}
+
+#[tokio::test]
+async fn test_fields_model_exposes_difficulty_model() {
+    let (status, body) =
+        build_and_send(Method::GET, "/?min=900&max=900&contest=abc&fields=model").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem["id"], "abc212_a");
+    assert_eq!(problem["model"]["slope"], -0.0005);
+    assert_eq!(problem["model"]["intercept"], 7.5);
+    assert_eq!(problem["model"]["variance"], 0.2);
+    assert_eq!(problem["model"]["discrimination"], 0.004);
+    assert_eq!(problem["model"]["is_experimental"], false);
+    assert_eq!(problem["model"]["irt_loglikelihood"], -120.5);
+}
+
+#[tokio::test]
+async fn test_unknown_field_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/?fields=model,history").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_value", Some("fields"), "'fields' contains an unknown field 'history'.");
+}
+
+#[tokio::test]
+async fn test_experimental_false_excludes_experimental_difficulty() {
+    let (status, body) = build_and_send(Method::GET, "/?min=1300&max=1300").await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+    }
+
+    let problem: ProblemResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem.id, "abc460_a");
+
+    let (status, _) = build_and_send(Method::GET, "/?min=1300&max=1300&experimental=false").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_invalid_experimental_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/?experimental=no").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_boolean", Some("experimental"), "'experimental' must be true or false.");
+}
+
+#[tokio::test]
+async fn test_rating_with_probability_window_selects_problem() {
+    let (status, body) = build_and_send(Method::GET, "/?rating=900&p_min=0.48&p_max=0.6").await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+        solve_probability: f64,
+    }
+
+    let problem: ProblemResponse = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem.id, "abc212_a");
+    assert!((problem.solve_probability - 0.5).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_rating_probability_window_combines_with_other_filters() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/?rating=900&p_min=0.4&p_max=0.6&contest=arc&count=1",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    #[derive(serde::Deserialize)]
+    struct ProblemResponse {
+        id: String,
+        solve_probability: f64,
+    }
+
+    let problems: Vec<ProblemResponse> = serde_json::from_str(&body).unwrap();
+    assert_eq!(problems[0].id, "arc212_a");
+    assert!(problems[0].solve_probability < 0.5);
+}
+
+#[tokio::test]
+async fn test_probability_window_without_rating_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/?p_min=0.3").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(
+        &body,
+        "missing_parameter",
+        Some("rating"),
+        "'rating' is required when 'p_min' or 'p_max' is specified.",
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_probability_window_is_bad_request() {
+    let (status, body) = build_and_send(Method::GET, "/?rating=1000&p_max=1.5").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "out_of_range", Some("p_max"), "'p_min' and 'p_max' must be between 0 and 1.");
+
+    let (status, body) = build_and_send(Method::GET, "/?rating=1000&p_min=0.6&p_max=0.3").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "min_greater_than_max", Some("p_min"), "'p_min' cannot be greater than 'p_max'.");
+}
+
+#[tokio::test]
+async fn test_probability_window_without_discrimination_is_unavailable() {
+    // 配布データのように識別力のないモデルだけの場合
+    let mut state = (*build_test_state()).clone();
+    for model in state.problem_models.values_mut() {
+        model.discrimination = None;
+    }
+
+    for path in ["/?rating=900&p_min=0.4&p_max=0.6", "/contest-set?bands=0-2000&rating=900"] {
+        let req = Request::builder()
+            .uri(path)
+            .header("accept-language", "en")
+            .body(Body::empty())
+            .unwrap();
+        let res = router(req, Arc::new(state.clone())).await.unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_error(
+            std::str::from_utf8(&body).unwrap(),
+            "model_unavailable",
+            Some("rating"),
+            "Solve probability is not available because the problem models have no discrimination.",
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_problem_lookup_returns_canonical_contest_and_url() {
+    let (status, body) = build_and_send(Method::GET, "/problems/abc212_b").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem["id"], "abc212_b");
+    assert_eq!(problem["contest_id"], "abc212");
+    assert_eq!(problem["name"], "B - Weak Password");
+    assert_eq!(problem["difficulty"], 925.0);
+    assert_eq!(problem["url"], "https://atcoder.jp/contests/abc212/tasks/abc212_b");
+    assert!(problem.get("model").is_none());
+}
+
+#[tokio::test]
+async fn test_problem_lookup_with_model_fields() {
+    let (status, body) = build_and_send(Method::GET, "/problems/abc212_a?fields=model").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem["model"]["discrimination"], 0.004);
+}
+
+#[tokio::test]
+async fn test_problem_lookup_unknown_id_is_not_found() {
+    let (status, body) = build_and_send(Method::GET, "/problems/abc999_z").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(&body, "problem_not_found", None, "Problem not found.");
+}
+
+async fn list_problems(path: &str) -> serde_json::Value {
+    let (status, body) = build_and_send(Method::GET, path).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    serde_json::from_str(&body).unwrap()
+}
+
+fn listed_ids(page: &serde_json::Value) -> Vec<String> {
+    page["problems"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|problem| problem["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_problem_list_uses_picker_filters() {
+    let page = list_problems("/problems?min=850&max=1000&contest=abc&exclude=abc212_b").await;
+
+    assert_eq!(page["total"], 3);
+    assert_eq!(listed_ids(&page), ["abc001_a", "abc212_a", "abc213_a"]);
+    assert!(page["next_cursor"].is_null());
+}
+
+#[tokio::test]
+async fn test_problem_list_sorting() {
+    let page = list_problems("/problems?sort=difficulty&contest=abc").await;
+    assert_eq!(
+        listed_ids(&page),
+        ["abc213_a", "abc212_a", "abc212_b", "abc001_a", "abc460_a", "abc459_a"]
+    );
+
+    let page = list_problems("/problems?sort=-difficulty&contest=abc").await;
+    assert_eq!(
+        listed_ids(&page),
+        ["abc460_a", "abc001_a", "abc212_b", "abc212_a", "abc213_a", "abc459_a"]
+    );
+
+    let page = list_problems("/problems?sort=contest&min=0").await;
+    assert_eq!(
+        listed_ids(&page),
+        [
+            "abc001_a",
+            "abc212_a",
+            "abc212_b",
+            "abc213_a",
+            "abc460_a",
+            "agc001_a",
+            "arc001_a",
+            "arc212_a",
+            "typical90_a"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_problem_list_cursor_pagination() {
+    let mut ids = Vec::new();
+    let mut path = "/problems?sort=difficulty&limit=4".to_string();
+    loop {
+        let page = list_problems(&path).await;
+        assert_eq!(page["total"], 10);
+        ids.extend(listed_ids(&page));
+
+        match page["next_cursor"].as_str() {
+            Some(cursor) => path = format!("/problems?sort=difficulty&limit=4&cursor={cursor}"),
+            None => break,
+        }
+    }
+
+    let all = list_problems("/problems?sort=difficulty&limit=200").await;
+    assert_eq!(ids, listed_ids(&all));
+}
+
+#[tokio::test]
+async fn test_problem_list_without_matches_is_empty() {
+    let page = list_problems("/problems?min=5000").await;
+
+    assert_eq!(page["total"], 0);
+    assert!(listed_ids(&page).is_empty());
+}
+
+#[tokio::test]
+async fn test_problem_list_validation() {
+    let (status, body) = build_and_send(Method::GET, "/problems?sort=name").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(
+        &body,
+        "invalid_value",
+        Some("sort"),
+        "'sort' must be one of difficulty, contest, id (prefix '-' for descending).",
+    );
+
+    let (status, body) = build_and_send(Method::GET, "/problems?limit=201").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "out_of_range", Some("limit"), "'limit' must be between 1 and 200.");
+
+    let (status, body) = build_and_send(Method::GET, "/problems?cursor=abc999_z").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_value", Some("cursor"), "'cursor' is invalid.");
+}
+
+#[tokio::test]
+async fn test_stats_counts_candidates() {
+    let (status, body) = build_and_send(Method::GET, "/stats").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let stats: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(stats["count"], 10);
+    assert_eq!(stats["unknown_difficulty"], 1);
+    assert_eq!(stats["bucket_width"], 400);
+    assert_eq!(
+        stats["histogram"],
+        serde_json::json!([
+            {"min": 400.0, "max": 800.0, "count": 1},
+            {"min": 800.0, "max": 1200.0, "count": 6},
+            {"min": 1200.0, "max": 1600.0, "count": 2}
+        ])
+    );
+    assert_eq!(
+        stats["contests"],
+        serde_json::json!({"abc": 6, "arc": 2, "agc": 1, "other": 1})
+    );
+}
+
+#[tokio::test]
+async fn test_stats_uses_same_filters_as_picker() {
+    let (status, body) = build_and_send(
+        Method::GET,
+        "/stats?min=800&max=1000&contest=abc&exclude=abc001_a&bucket_width=100",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let stats: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(stats["count"], 3);
+    assert_eq!(stats["unknown_difficulty"], 0);
+    assert_eq!(
+        stats["histogram"],
+        serde_json::json!([
+            {"min": 800.0, "max": 900.0, "count": 1},
+            {"min": 900.0, "max": 1000.0, "count": 2}
+        ])
+    );
+    assert_eq!(stats["contests"]["abc"], 3);
+}
+
+#[tokio::test]
+async fn test_stats_without_matches_and_validation() {
+    let (status, body) = build_and_send(Method::GET, "/stats?min=5000").await;
+    assert_eq!(status, StatusCode::OK);
+    let stats: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(stats["count"], 0);
+    assert!(stats["histogram"].as_array().unwrap().is_empty());
+
+    let (status, body) = build_and_send(Method::GET, "/stats?bucket_width=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "out_of_range", Some("bucket_width"), "'bucket_width' must be between 1 and 4000.");
+}
+
+async fn post_json(path: &str, body: &str) -> (StatusCode, String) {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header("accept-language", "en")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let res = router(req, build_test_state()).await.unwrap();
+
+    let status = res.status();
+    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    (status, String::from_utf8(body_bytes.to_vec()).unwrap())
+}
+
+fn sorted_ids(body: &str) -> Vec<String> {
+    let problems: Vec<serde_json::Value> = serde_json::from_str(body).unwrap();
+    let mut ids = problems
+        .iter()
+        .map(|problem| problem["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn test_pick_with_multiple_difficulty_ranges() {
+    let (status, body) = post_json(
+        "/pick",
+        r#"{"contests":["abc"],"difficulty":[{"max":860},{"min":950,"max":1000}],"count":2,"seed":1}"#,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sorted_ids(&body), ["abc001_a", "abc213_a"]);
+}
+
+#[tokio::test]
+async fn test_pick_with_nested_rules() {
+    let (status, body) = post_json(
+        "/pick",
+        r#"{
+            "difficulty": [{"min": 800, "max": 1200}],
+            "rules": {
+                "include": [{"contests": ["arc"]}, {"problems": ["abc001_a"]}],
+                "exclude": [{"contest_from": 200}]
+            },
+            "count": 2
+        }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sorted_ids(&body), ["abc001_a", "arc001_a"]);
+
+    // 入れ子の exclude で、外側の exclude から arc212_a だけを外す
+    let (status, body) = post_json(
+        "/pick",
+        r#"{
+            "contests": ["arc"],
+            "rules": {
+                "exclude": [{"contest_from": 1, "rules": {"exclude": [{"problems": ["arc212_a"]}]}}]
+            }
+        }"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(problem["id"], "arc212_a");
+}
+
+#[tokio::test]
+async fn test_pick_exclude_is_not_limited_to_twenty_ids() {
+    let mut exclude = (0..40)
+        .map(|i| format!("\"abc999_{}\"", i))
+        .collect::<Vec<_>>();
+    exclude.push("\"abc001_a\"".to_string());
+    let body = format!(
+        r#"{{"rules":{{"include":[{{"problems":["abc001_a","arc001_a"]}}]}},"exclude":[{}],"count":1}}"#,
+        exclude.join(",")
+    );
+
+    let (status, body) = post_json("/pick", &body).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(sorted_ids(&body), ["arc001_a"]);
+}
+
+#[tokio::test]
+async fn test_pick_reports_not_enough_problems() {
+    let (status, body) = post_json(
+        "/pick",
+        r#"{"rules":{"include":[{"problems":["abc001_a","arc001_a"]}]},"count":3}"#,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(
+        &body,
+        "not_enough_candidates",
+        Some("count"),
+        "Only 2 problems match the conditions (3 requested).",
+    );
+}
+
+#[tokio::test]
+async fn test_pick_validation_reports_field_paths() {
+    let cases = [
+        ("[]", "invalid_body", None, "Request body must be a JSON object."),
+        ("{", "invalid_body", None, "Request body must be valid JSON."),
+        (
+            r#"{"colour":"red"}"#,
+            "unknown_field",
+            Some("colour"),
+            "'colour' is not a known field.",
+        ),
+        (
+            r#"{"count":0}"#,
+            "out_of_range",
+            Some("count"),
+            "'count' must be between 1 and 50.",
+        ),
+        (
+            r#"{"difficulty":[{"min":100},{"min":900,"max":800}]}"#,
+            "min_greater_than_max",
+            Some("difficulty[1].min"),
+            "'difficulty[1].min' cannot be greater than 'difficulty[1].max'.",
+        ),
+        (
+            r#"{"difficulty":[{"min":"low"}]}"#,
+            "invalid_number",
+            Some("difficulty[0].min"),
+            "'difficulty[0].min' must be a number.",
+        ),
+        (
+            r#"{"rules":{"include":[{"contests":[1]}]}}"#,
+            "invalid_value",
+            Some("rules.include[0].contests[0]"),
+            "'rules.include[0].contests[0]' must be a non-empty string.",
+        ),
+        (
+            r#"{"rules":{"exclude":[{"contest_from":300,"contest_to":100}]}}"#,
+            "min_greater_than_max",
+            Some("rules.exclude[0].contest_from"),
+            "'rules.exclude[0].contest_from' cannot be greater than 'rules.exclude[0].contest_to'.",
+        ),
+        (
+            r#"{"exclude":["abc/001"]}"#,
+            "invalid_exclude_id",
+            Some("exclude[0]"),
+            "'exclude[0]' is not a valid problem ID.",
+        ),
+        (
+            r#"{"solve_probability":{"min":0.5}}"#,
+            "missing_parameter",
+            Some("solve_probability.rating"),
+            "'solve_probability.rating' is required.",
+        ),
+        (
+            r#"{"rules":{"include":[{"rules":{"include":[{"rules":{"include":[{"rules":{}}]}}]}}]}}"#,
+            "too_many_values",
+            Some("rules.include[0].rules.include[0].rules.include[0].rules"),
+            "'rules.include[0].rules.include[0].rules.include[0].rules' cannot be nested more than 3 levels.",
+        ),
+    ];
+
+    for (request, code, param, message) in cases {
+        let (status, body) = post_json("/pick", request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", request);
+        assert_error(&body, code, param, message);
+    }
+
+    let (status, body) = post_json("/pick", &"x".repeat(17 * 1024)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "body_too_large", None, "Request body must be at most 16384 bytes.");
+}
+
+async fn send_with_accept_language(path: &str, accept_language: &str) -> (StatusCode, String) {
+    let req = Request::builder()
+        .uri(path)
+        .header("accept-language", accept_language)
+        .body(Body::empty())
+        .unwrap();
+
+    let res = router(req, build_test_state()).await.unwrap();
+    let status = res.status();
+    let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+    (status, String::from_utf8(body_bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_errors_follow_accept_language() {
+    let (status, body) = send_with_accept_language("/?min=0&max=500", "ja-JP,ja;q=0.9,en;q=0.8").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "指定Diff範囲に該当する問題がありませんでした",
+    );
+
+    let (status, body) = send_with_accept_language("/?min=abc", "ja").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_error(&body, "invalid_number", Some("min"), "'min' には数値を指定してください");
+
+    let (_, body) = send_with_accept_language("/?min=0&max=500", "en-US,ja;q=0.5").await;
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
+
+    // 対応していない言語だけなら日本語にする
+    let (_, body) = send_with_accept_language("/test", "fr-FR,de;q=0.8").await;
+    assert_error(&body, "not_found", None, "ページが見つかりません");
+}
+
+#[tokio::test]
+async fn test_errors_default_to_japanese() {
+    let req = Request::builder().uri("/?min=0&max=500").body(Body::empty()).unwrap();
+    let res = router(req, build_test_state()).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+    assert_error(
+        std::str::from_utf8(&body).unwrap(),
+        "no_candidates",
+        None,
+        "指定Diff範囲に該当する問題がありませんでした",
+    );
+}
+
+#[tokio::test]
+async fn test_lang_parameter_overrides_accept_language() {
+    let (_, body) = send_with_accept_language("/?min=0&max=500&lang=en", "ja").await;
+    assert_error(
+        &body,
+        "no_candidates",
+        None,
+        "No problems match the difficulty range.",
+    );
+
+    let (_, body) = build_and_send(Method::GET, "/test?lang=ja").await;
+    assert_error(&body, "not_found", None, "ページが見つかりません");
+
+    let (status, body) = post_json(
+        "/pick?lang=ja",
+        r#"{"problems":["abc001_a","arc001_a"],"count":3}"#,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_error(
+        &body,
+        "not_enough_candidates",
+        Some("count"),
+        "条件に一致する問題が2問しかありません（3問を要求しました）",
+    );
 }
@@ -0,0 +1,254 @@
+mod common;
+
+use backend::utils::routing::{AppState, Services};
+use backend::utils::saved::{hash_user_token, SaveOutcome, SavedProblems, MAX_USER_TOKENS};
+use backend::utils::storage::{MemoryStorage, Record, Storage};
+use common::{build_state, request_builder};
+use hyper::{Body, Method, StatusCode};
+use std::sync::Arc;
+
+const TOKEN: &str = "laptop-and-phone-0123";
+
+fn build_test_state() -> Arc<AppState> {
+    Arc::new(build_state(&[
+        ("abc001_a", "abc001", Some(-500.0)),
+        ("abc212_a", "arc999", None),
+    ]))
+}
+
+async fn send(
+    method: Method,
+    path: &str,
+    token: Option<&str>,
+    body: &str,
+    services: &Services,
+) -> (StatusCode, serde_json::Value) {
+    let mut builder = request_builder(method, path);
+    if let Some(token) = token {
+        builder = builder.header("X-User-Token", token);
+    }
+    let req = builder.body(Body::from(body.to_string())).unwrap();
+    let (status, _, body) = common::send(req, build_test_state(), services).await;
+    let body = serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body));
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_saved_problem_is_enriched_from_problem_data() {
+    let services = Services::default();
+
+    let (status, saved) = send(
+        Method::PUT,
+        "/saved/abc212_a",
+        Some(TOKEN),
+        r#"{"note":"  解き直す  ","tags":["dp","dp"," greedy "]}"#,
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(saved["id"], "abc212_a");
+    assert_eq!(saved["contest_id"], "abc212");
+    assert_eq!(saved["name"], "abc212_a");
+    assert!(saved["difficulty"].is_null());
+    assert_eq!(saved["note"], "解き直す");
+    assert_eq!(saved["tags"], serde_json::json!(["dp", "greedy"]));
+
+    let (status, saved) = send(Method::PUT, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(saved["difficulty"], -500.0);
+    assert!(saved["note"].is_null());
+
+    let (status, list) = send(Method::GET, "/saved", Some(TOKEN), "", &services).await;
+    assert_eq!(status, StatusCode::OK);
+    let ids = list
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|saved| saved["id"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["abc212_a", "abc001_a"]);
+}
+
+#[tokio::test]
+async fn test_saving_again_updates_note_and_keeps_saved_at() {
+    let services = Services::default();
+
+    let (_, first) = send(
+        Method::PUT,
+        "/saved/abc001_a",
+        Some(TOKEN),
+        r#"{"note":"old"}"#,
+        &services,
+    )
+    .await;
+    let (status, second) = send(
+        Method::PUT,
+        "/saved/abc001_a",
+        Some(TOKEN),
+        r#"{"tags":["review"]}"#,
+        &services,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(second["note"].is_null());
+    assert_eq!(second["tags"], serde_json::json!(["review"]));
+    assert_eq!(second["saved_at"], first["saved_at"]);
+
+    let (status, saved) = send(Method::GET, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(saved["tags"], serde_json::json!(["review"]));
+}
+
+#[tokio::test]
+async fn test_delete_saved_problem() {
+    let services = Services::default();
+    send(Method::PUT, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+
+    let (status, _) = send(Method::DELETE, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = send(Method::DELETE, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_saved");
+    assert_eq!(body["message"], "This problem is not saved.");
+
+    let (_, list) = send(Method::GET, "/saved", Some(TOKEN), "", &services).await;
+    assert!(list.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_saved_problems_are_separated_by_token() {
+    let services = Services::default();
+    send(Method::PUT, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+
+    let (status, list) = send(Method::GET, "/saved", Some("another-device-token"), "", &services).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(list.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_saved_requires_valid_token() {
+    let services = Services::default();
+
+    let (status, body) = send(Method::GET, "/saved", None, "", &services).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "missing_user_token");
+    assert_eq!(body["message"], "A user token is required.");
+
+    let (status, body) = send(Method::GET, "/saved", Some("short"), "", &services).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "invalid_user_token");
+    assert_eq!(body["message"], "Invalid user token.");
+}
+
+#[tokio::test]
+async fn test_save_validation() {
+    let services = Services::default();
+
+    let (status, body) = send(Method::PUT, "/saved/abc999_z", Some(TOKEN), "", &services).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Problem not found.");
+
+    let (status, body) = send(Method::PUT, "/saved/abc001_a", Some(TOKEN), "[]", &services).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_body");
+    assert_eq!(
+        body["message"],
+        "Request body must be a JSON object with optional 'note' and 'tags'."
+    );
+
+    let note = format!(r#"{{"note":"{}"}}"#, "あ".repeat(501));
+    let (status, body) = send(Method::PUT, "/saved/abc001_a", Some(TOKEN), &note, &services).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["param"], "note");
+    assert_eq!(body["message"], "'note' must be at most 500 characters.");
+
+    let (status, body) = send(
+        Method::PUT,
+        "/saved/abc001_a",
+        Some(TOKEN),
+        r#"{"tags":[""]}"#,
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["param"], "tags");
+    assert_eq!(body["message"], "Each tag must be between 1 and 32 characters.");
+
+    let (status, body) = send(
+        Method::PUT,
+        "/saved/abc001_a",
+        Some(TOKEN),
+        &"x".repeat(17 * 1024),
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "body_too_large");
+}
+
+#[tokio::test]
+async fn test_saved_problems_survive_restart() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let services = Services {
+        saved_problems: Arc::new(SavedProblems::with_storage(storage.clone()).unwrap()),
+        ..Services::default()
+    };
+    send(Method::PUT, "/saved/abc001_a", Some(TOKEN), r#"{"note":"memo"}"#, &services).await;
+    send(Method::PUT, "/saved/abc212_a", Some(TOKEN), "", &services).await;
+    send(Method::DELETE, "/saved/abc212_a", Some(TOKEN), "", &services).await;
+
+    let restarted = Services {
+        saved_problems: Arc::new(SavedProblems::with_storage(storage.clone()).unwrap()),
+        ..Services::default()
+    };
+    let (_, list) = send(Method::GET, "/saved", Some(TOKEN), "", &restarted).await;
+
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["note"], "memo");
+    assert_eq!(storage.load().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_tokens_are_stored_as_hashes() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let services = Services {
+        saved_problems: Arc::new(SavedProblems::with_storage(storage.clone()).unwrap()),
+        ..Services::default()
+    };
+    send(Method::PUT, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+    send(Method::DELETE, "/saved/abc001_a", Some(TOKEN), "", &services).await;
+
+    let records = storage.load().unwrap();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        let token = match record {
+            Record::ProblemSaved { token, .. } | Record::ProblemUnsaved { token, .. } => token,
+            record => panic!("unexpected record: {:?}", record),
+        };
+        assert_eq!(token, hash_user_token(TOKEN));
+    }
+}
+
+#[test]
+fn test_new_tokens_are_rejected_when_full() {
+    let saved_problems = SavedProblems::default();
+    for i in 0..MAX_USER_TOKENS {
+        let token = format!("user-token-{:016}", i);
+        assert!(matches!(
+            saved_problems.save(&token, "abc001_a", None, Vec::new()),
+            SaveOutcome::Created(_)
+        ));
+    }
+
+    assert_eq!(
+        saved_problems.save("new-user-token-0123", "abc001_a", None, Vec::new()),
+        SaveOutcome::TooManyTokens
+    );
+    // 保存済みのトークンは引き続き使える
+    assert!(matches!(
+        saved_problems.save(&format!("user-token-{:016}", 0), "abc212_a", None, Vec::new()),
+        SaveOutcome::Created(_)
+    ));
+}
@@ -0,0 +1,240 @@
+mod common;
+
+use backend::utils::routing::{AppState, Services};
+use backend::utils::session::{SessionStore, MAX_SESSIONS};
+use hyper::{header, Body, Method, Request, StatusCode};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::{build_state, request};
+
+const PROBLEM_COUNT: usize = 30;
+
+fn build_test_state() -> Arc<AppState> {
+    let problems = (1..=PROBLEM_COUNT)
+        .map(|number| (format!("abc{:03}_a", number), format!("abc{:03}", number)))
+        .collect::<Vec<_>>();
+    let problems = problems
+        .iter()
+        .map(|(id, contest_id)| (id.as_str(), contest_id.as_str(), Some(800.0)))
+        .collect::<Vec<_>>();
+    Arc::new(build_state(&problems))
+}
+
+async fn send(req: Request<Body>, services: &Services) -> (StatusCode, hyper::HeaderMap, String) {
+    common::send(req, build_test_state(), services).await
+}
+
+async fn create_session(services: &Services) -> String {
+    let (status, _, body) = send(request(Method::POST, "/session"), services).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    body["session_id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_create_session_returns_id_header_and_cookie() {
+    let services = Services::default();
+    let (status, headers, body) = send(request(Method::POST, "/session"), &services).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let session_id = body["session_id"].as_str().unwrap();
+    assert_eq!(session_id.len(), 32);
+    assert_eq!(headers["x-session-id"], session_id);
+
+    let cookie = headers[header::SET_COOKIE].to_str().unwrap();
+    assert!(cookie.starts_with(&format!("session_id={session_id};")));
+    assert!(cookie.contains("HttpOnly"));
+}
+
+#[tokio::test]
+async fn test_session_never_repeats_problems_beyond_exclude_limit() {
+    let services = Services::default();
+    let session_id = create_session(&services).await;
+
+    let mut picked = HashSet::new();
+    for _ in 0..PROBLEM_COUNT {
+        let (status, _, body) = send(
+            request(Method::GET, &format!("/?session={session_id}")),
+            &services,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let problem: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(picked.insert(problem["id"].as_str().unwrap().to_string()));
+    }
+
+    let (status, _, _) = send(
+        request(Method::GET, &format!("/?session={session_id}")),
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_session_history_lists_and_clears_picks() {
+    let services = Services::default();
+    let session_id = create_session(&services).await;
+
+    let (_, _, body) = send(
+        request(Method::GET, &format!("/?session={session_id}&count=3")),
+        &services,
+    )
+    .await;
+    let picked: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+    let (status, _, body) = send(
+        request(
+            Method::GET,
+            &format!("/session/history?session={session_id}"),
+        ),
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let history: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let history = history["history"].as_array().unwrap();
+    assert_eq!(history.len(), 3);
+    for (entry, problem) in history.iter().zip(picked.as_array().unwrap()) {
+        assert_eq!(entry["problem_id"], problem["id"]);
+        assert!(entry["picked_at"].is_string());
+    }
+
+    let (status, _, _) = send(
+        request(
+            Method::DELETE,
+            &format!("/session/history?session={session_id}"),
+        ),
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, _, body) = send(
+        request(
+            Method::GET,
+            &format!("/session/history?session={session_id}"),
+        ),
+        &services,
+    )
+    .await;
+    let history: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert!(history["history"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_session_can_be_sent_by_header_or_cookie() {
+    let services = Services::default();
+    let session_id = create_session(&services).await;
+
+    let by_header = Request::builder()
+        .uri("/")
+        .header("X-Session-Id", &session_id)
+        .body(Body::empty())
+        .unwrap();
+    let (status, _, _) = send(by_header, &services).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let by_cookie = Request::builder()
+        .uri("/session/history")
+        .header(
+            header::COOKIE,
+            format!("theme=dark; session_id={session_id}"),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let (status, _, body) = send(by_cookie, &services).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let history: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(history["history"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_or_unknown_session() {
+    let services = Services::default();
+
+    let (status, _, body) = send(request(Method::GET, "/?session=xyz"), &services).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let err: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(err["code"], "invalid_value");
+    assert_eq!(err["param"], "session");
+    assert_eq!(err["message"], "'session' must be a valid session ID.");
+
+    let (status, _, body) = send(
+        request(Method::GET, &format!("/?session={}", "0".repeat(32))),
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let err: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(err["code"], "session_not_found");
+    assert_eq!(
+        err["message"],
+        "Session not found. Please start a new session."
+    );
+
+    let (status, _, body) = send(request(Method::GET, "/session/history"), &services).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let err: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(err["code"], "missing_parameter");
+    assert_eq!(err["message"], "'session' is required.");
+}
+
+#[tokio::test]
+async fn test_expired_session_is_discarded() {
+    let services = Services {
+        sessions: Arc::new(SessionStore::new(Duration::ZERO)),
+        ..Services::default()
+    };
+    let session_id = create_session(&services).await;
+    tokio::time::sleep(Duration::from_millis(5)).await;
+
+    let (status, _, _) = send(
+        request(Method::GET, &format!("/?session={session_id}")),
+        &services,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_reading_history_does_not_extend_session() {
+    let sessions = SessionStore::new(Duration::from_millis(200));
+    let session_id = sessions.create().unwrap();
+
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert!(sessions.history(&session_id).is_some());
+    // 履歴を読んでも保持期間は延びない
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert!(sessions.history(&session_id).is_none());
+}
+
+#[test]
+fn test_very_long_retention_does_not_overflow() {
+    let sessions = SessionStore::new(Duration::MAX);
+    let session_id = sessions.create().unwrap();
+
+    assert!(sessions.contains(&session_id));
+    assert!(sessions.history(&session_id).is_some());
+}
+
+#[tokio::test]
+async fn test_new_sessions_are_rejected_when_full() {
+    let services = Services::default();
+    for _ in 0..MAX_SESSIONS {
+        services.sessions.create().unwrap();
+    }
+
+    let (status, headers, body) = send(request(Method::POST, "/session"), &services).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(headers.get(header::SET_COOKIE).is_none());
+    let error: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(error["code"], "session_limit_exceeded");
+    assert_eq!(error["message"], "Cannot start a new session right now. Try again later.");
+}
@@ -0,0 +1,231 @@
+mod common;
+
+use backend::utils::daily::{DailyProblems, MAX_RECORDS};
+use backend::utils::routing::{AppState, Services};
+use backend::utils::session::SessionStore;
+use backend::utils::storage::{FileStorage, MemoryStorage, Record, Storage};
+use chrono::{Duration as ChronoDuration, Utc};
+use common::{build_abc001_state, request, send, temp_state_file};
+use hyper::Method;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn send_daily(query: &str, state: Arc<AppState>, services: Services) -> String {
+    let path = format!("/daily?date={}{}", Utc::now().date_naive(), query);
+    let (_, _, body) = send(request(Method::GET, &path), state, &services).await;
+    let daily: serde_json::Value = serde_json::from_str(&body).unwrap();
+    daily["id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_file_storage_appends_and_loads_records() {
+    let path = temp_state_file("roundtrip");
+    let storage = FileStorage::new(path.clone());
+    assert!(storage.load().unwrap().is_empty());
+
+    let created = Record::SessionCreated {
+        session_id: "a".repeat(32),
+        created_at: Utc::now(),
+    };
+    let cleared = Record::HistoryCleared {
+        session_id: "a".repeat(32),
+        cleared_at: Utc::now(),
+    };
+    storage.append(&created).unwrap();
+    // 書き込み途中で停止した行は読み飛ばす
+    fs::write(
+        &path,
+        format!("{}{{\"type\":\"session_cr", fs::read_to_string(&path).unwrap()),
+    )
+    .unwrap();
+    assert_eq!(storage.load().unwrap(), vec![created.clone()]);
+
+    storage.rewrite(std::slice::from_ref(&created)).unwrap();
+    storage.append(&cleared).unwrap();
+    assert_eq!(FileStorage::new(path.clone()).load().unwrap(), [created, cleared]);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_sessions_survive_restart() {
+    let path = temp_state_file("sessions");
+    let retention = Duration::from_secs(60);
+
+    let store = SessionStore::with_storage(retention, Arc::new(FileStorage::new(path.clone()))).unwrap();
+    let session_id = store.create().unwrap();
+    let cleared_id = store.create().unwrap();
+    store.record(&session_id, ["abc001_a".to_string(), "abc001_b".to_string()]);
+    store.record(&session_id, ["abc001_c".to_string()]);
+    store.record(&cleared_id, ["abc001_a".to_string()]);
+    store.clear(&cleared_id);
+    drop(store);
+
+    let restored = SessionStore::with_storage(retention, Arc::new(FileStorage::new(path.clone()))).unwrap();
+    let history = restored
+        .history(&session_id)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.problem_id)
+        .collect::<Vec<_>>();
+    assert_eq!(history, ["abc001_a", "abc001_b", "abc001_c"]);
+    assert!(restored.history(&cleared_id).unwrap().is_empty());
+
+    // 起動時の整理後も同じ状態を復元できる
+    let compacted = SessionStore::with_storage(retention, Arc::new(FileStorage::new(path.clone()))).unwrap();
+    assert_eq!(compacted.history(&session_id).unwrap().len(), 3);
+    assert!(compacted.contains(&cleared_id));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_expired_sessions_are_removed_from_storage() {
+    let storage = Arc::new(MemoryStorage::new());
+    storage
+        .append(&Record::SessionCreated {
+            session_id: "b".repeat(32),
+            created_at: Utc::now() - ChronoDuration::days(2),
+        })
+        .unwrap();
+
+    let store = SessionStore::with_storage(Duration::from_secs(24 * 60 * 60), storage.clone()).unwrap();
+
+    assert!(!store.contains(&"b".repeat(32)));
+    assert!(storage.load().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_daily_problem_is_kept_across_restarts_and_data_updates() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let ids = ["abc001_a", "abc001_b", "abc001_c", "abc001_d", "abc001_e"];
+
+    let mut picked = Vec::new();
+    for state in [build_abc001_state(&ids), build_abc001_state(&[ids[4], ids[3], ids[2], ids[1], ids[0]])] {
+        let services = Services {
+            daily_problems: Arc::new(DailyProblems::with_storage(storage.clone()).unwrap()),
+            ..Services::default()
+        };
+        picked.push(send_daily("", state, services).await);
+    }
+
+    assert_eq!(picked[0], picked[1]);
+    assert_eq!(storage.load().unwrap().len(), 1);
+}
+
+#[test]
+fn test_old_daily_records_are_dropped() {
+    let storage = Arc::new(MemoryStorage::new());
+    storage
+        .append(&Record::DailyProblem {
+            date: Utc::now().date_naive() - ChronoDuration::days(365),
+            key: "old".to_string(),
+            problem_id: "abc001_a".to_string(),
+        })
+        .unwrap();
+
+    let daily_problems = DailyProblems::with_storage(storage.clone()).unwrap();
+
+    assert!(daily_problems.get("old").is_none());
+    assert!(storage.load().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_daily_key_ignores_how_filters_are_written() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let services = Services {
+        daily_problems: Arc::new(DailyProblems::with_storage(storage.clone()).unwrap()),
+        ..Services::default()
+    };
+    let state = build_abc001_state(&["abc001_a", "abc001_b", "abc001_c", "abc001_d"]);
+
+    let first = send_daily("&min=500&contest=arc,abc", state.clone(), services.clone()).await;
+    let second = send_daily("&min=500.0&contest=abc,arc,abc", state.clone(), services.clone()).await;
+    assert_eq!(first, second);
+    assert_eq!(storage.load().unwrap().len(), 1);
+
+    // 条件が違えば別の記録になる
+    send_daily("&min=500&contest=abc,arc&experimental=false", state, services).await;
+    assert_eq!(storage.load().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_daily_record_is_not_overwritten() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let services = Services {
+        daily_problems: Arc::new(DailyProblems::with_storage(storage.clone()).unwrap()),
+        ..Services::default()
+    };
+    let ids = ["abc001_a", "abc001_b", "abc001_c", "abc001_d"];
+
+    let recorded = send_daily("", build_abc001_state(&ids), services.clone()).await;
+    // 記録した問題が候補から外れている間は別の問題を返すが、記録は残す
+    let others = ids.iter().copied().filter(|id| *id != recorded).collect::<Vec<_>>();
+    let fallback = send_daily("", build_abc001_state(&others), services.clone()).await;
+    assert_ne!(fallback, recorded);
+    assert_eq!(send_daily("", build_abc001_state(&ids), services).await, recorded);
+    assert_eq!(storage.load().unwrap().len(), 1);
+}
+
+#[test]
+fn test_old_daily_records_are_dropped_at_runtime() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let daily_problems = DailyProblems::with_storage(storage.clone()).unwrap();
+    let today = Utc::now().date_naive();
+    daily_problems.record(today, "today", "abc001_a");
+    daily_problems.record(today - ChronoDuration::days(1), "yesterday", "abc001_b");
+
+    daily_problems.prune(today + ChronoDuration::days(30)).unwrap();
+
+    assert_eq!(daily_problems.get("today").as_deref(), Some("abc001_a"));
+    assert!(daily_problems.get("yesterday").is_none());
+    assert_eq!(storage.load().unwrap().len(), 1);
+}
+
+#[test]
+fn test_daily_records_outside_retention_are_not_kept() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let daily_problems = DailyProblems::with_storage(storage.clone()).unwrap();
+    let today = Utc::now().date_naive();
+    daily_problems.record(today - ChronoDuration::days(365), "past", "abc001_a");
+    daily_problems.record(today + ChronoDuration::days(365), "future", "abc001_b");
+
+    assert!(daily_problems.get("past").is_none());
+    assert!(daily_problems.get("future").is_none());
+    assert!(storage.load().unwrap().is_empty());
+}
+
+#[test]
+fn test_daily_keys_are_stored_as_hashes() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let daily_problems = DailyProblems::with_storage(storage.clone()).unwrap();
+    let key = "contest=abc".repeat(100);
+    daily_problems.record(Utc::now().date_naive(), &key, "abc001_a");
+
+    match &storage.load().unwrap()[0] {
+        Record::DailyProblem { key: stored, .. } => {
+            assert_eq!(stored.len(), 64);
+            assert!(!stored.contains("contest"));
+        }
+        record => panic!("unexpected record: {:?}", record),
+    }
+    assert_eq!(daily_problems.get(&key).as_deref(), Some("abc001_a"));
+}
+
+#[test]
+fn test_oldest_daily_records_are_evicted_when_full() {
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let daily_problems = DailyProblems::with_storage(storage.clone()).unwrap();
+    let today = Utc::now().date_naive();
+    daily_problems.record(today - ChronoDuration::days(1), "oldest", "abc001_a");
+    for i in 1..MAX_RECORDS {
+        daily_problems.record(today, &format!("key{}", i), "abc001_b");
+    }
+
+    daily_problems.record(today, "newest", "abc001_c");
+
+    assert!(daily_problems.get("oldest").is_none());
+    assert_eq!(daily_problems.get("newest").as_deref(), Some("abc001_c"));
+    assert!(storage.load().unwrap().len() < MAX_RECORDS);
+}
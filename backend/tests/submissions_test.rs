@@ -0,0 +1,332 @@
+mod common;
+
+use backend::utils::routing::{AppState, Services, SOLVED_COMPLETE_HEADER};
+use backend::utils::submissions::{
+    FileSubmissionSource, HttpSubmissionSource, SolvedProblems, SourceFuture, Submission,
+    SubmissionSource, MAX_CACHED_USERS, MAX_PAGES_PER_REQUEST, SUBMISSIONS_PER_PAGE,
+};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, HeaderMap, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{build_abc001_state, request};
+
+/// 呼び出し時の from_second を記録し、それ以降の提出を 1 ページ分返すモック
+struct MockSource {
+    submissions: Mutex<Vec<(u64, String, &'static str)>>,
+    calls: Mutex<Vec<u64>>,
+    // この回数だけ成功した後は失敗する
+    fails_after: Option<usize>,
+}
+
+impl MockSource {
+    fn new(submissions: Vec<(u64, &'static str, &'static str)>) -> Arc<Self> {
+        Arc::new(Self {
+            submissions: Mutex::new(
+                submissions
+                    .into_iter()
+                    .map(|(epoch_second, problem_id, result)| (epoch_second, problem_id.to_string(), result))
+                    .collect(),
+            ),
+            calls: Mutex::new(Vec::new()),
+            fails_after: None,
+        })
+    }
+}
+
+impl SubmissionSource for MockSource {
+    fn fetch<'a>(&'a self, _user: &'a str, from_second: u64) -> SourceFuture<'a, Vec<Submission>> {
+        Box::pin(async move {
+            let calls = {
+                let mut calls = self.calls.lock().unwrap();
+                calls.push(from_second);
+                calls.len()
+            };
+            // 同時に呼ばれた場合に他のリクエストへ処理を譲る
+            tokio::task::yield_now().await;
+            if self.fails_after.is_some_and(|fails_after| calls > fails_after) {
+                return Err("upstream is down".into());
+            }
+
+            Ok(self
+                .submissions
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(epoch_second, _, _)| *epoch_second >= from_second)
+                .take(SUBMISSIONS_PER_PAGE)
+                .map(|(epoch_second, problem_id, result)| Submission {
+                    epoch_second: *epoch_second,
+                    problem_id: problem_id.clone(),
+                    result: result.to_string(),
+                })
+                .collect())
+        })
+    }
+}
+
+fn build_test_state() -> Arc<AppState> {
+    build_abc001_state(&["abc001_a", "abc001_b", "abc001_c"])
+}
+
+async fn send(path: &str, services: Services) -> (StatusCode, HeaderMap, String) {
+    common::send(request(Method::GET, path), build_test_state(), &services).await
+}
+
+fn services_with(source: Arc<dyn SubmissionSource>) -> Services {
+    Services {
+        solved_problems: Some(Arc::new(SolvedProblems::new(source))),
+        ..Services::default()
+    }
+}
+
+#[tokio::test]
+async fn test_solved_problems_only_counts_accepted_submissions() {
+    let source = MockSource::new(vec![(10, "abc001_a", "AC"), (20, "abc001_b", "WA")]);
+    let solved = SolvedProblems::new(source).solved("alice").await.unwrap();
+
+    assert!(solved.complete);
+    assert!(solved.problems.contains("abc001_a"));
+    assert!(!solved.problems.contains("abc001_b"));
+}
+
+#[tokio::test]
+async fn test_solved_problems_are_cached_and_refreshed_incrementally() {
+    let source = MockSource::new(vec![(10, "abc001_a", "AC"), (20, "abc001_b", "WA")]);
+    let solved_problems = SolvedProblems::with_refresh_interval(source.clone(), Duration::ZERO);
+
+    solved_problems.solved("alice").await.unwrap();
+    source
+        .submissions
+        .lock()
+        .unwrap()
+        .push((30, "abc001_b".to_string(), "AC"));
+    let solved = solved_problems.solved("alice").await.unwrap();
+
+    // 同じ秒の提出を取りこぼさないよう、最後の提出の秒から取得し直す
+    assert_eq!(*source.calls.lock().unwrap(), [0, 20]);
+    assert!(solved.problems.contains("abc001_a"));
+    assert!(solved.problems.contains("abc001_b"));
+
+    let cached = SolvedProblems::with_refresh_interval(source.clone(), Duration::from_secs(60));
+    cached.solved("bob").await.unwrap();
+    cached.solved("bob").await.unwrap();
+    assert_eq!(source.calls.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_many_submissions_are_fetched_over_several_requests() {
+    let pages = MAX_PAGES_PER_REQUEST + 1;
+    let submissions = (0..pages * SUBMISSIONS_PER_PAGE)
+        .map(|i| (i as u64, format!("p{}", i), "AC"))
+        .collect();
+    let source = Arc::new(MockSource {
+        submissions: Mutex::new(submissions),
+        calls: Mutex::new(Vec::new()),
+        fails_after: None,
+    });
+    let solved_problems = SolvedProblems::with_refresh_interval(source.clone(), Duration::from_secs(60));
+
+    // 1 リクエストで取得するページ数には上限があり、途中までの結果を返す
+    let solved = solved_problems.solved("alice").await.unwrap();
+    assert_eq!(source.calls.lock().unwrap().len(), MAX_PAGES_PER_REQUEST);
+    assert!(!solved.complete);
+    assert!(solved.problems.len() > (MAX_PAGES_PER_REQUEST - 1) * SUBMISSIONS_PER_PAGE);
+
+    // 残りは更新間隔を待たずに次のリクエストで取得する
+    let solved = solved_problems.solved("alice").await.unwrap();
+    assert!(solved.complete);
+    assert_eq!(solved.problems.len(), pages * SUBMISSIONS_PER_PAGE);
+    let calls = source.calls.lock().unwrap().len();
+    solved_problems.solved("alice").await.unwrap();
+    assert_eq!(source.calls.lock().unwrap().len(), calls);
+}
+
+#[tokio::test]
+async fn test_submissions_in_the_same_second_across_pages_are_kept() {
+    // 2 ページ目の先頭が 1 ページ目の最後と同じ秒になる
+    let mut submissions = (0..SUBMISSIONS_PER_PAGE)
+        .map(|i| (i as u64, format!("p{}", i), "AC"))
+        .collect::<Vec<_>>();
+    let last = submissions.last().unwrap().0;
+    submissions.push((last, "same_second".to_string(), "AC"));
+    let source = Arc::new(MockSource {
+        submissions: Mutex::new(submissions),
+        calls: Mutex::new(Vec::new()),
+        fails_after: None,
+    });
+
+    let solved = SolvedProblems::new(source).solved("alice").await.unwrap();
+
+    assert!(solved.complete);
+    assert!(solved.problems.contains("same_second"));
+    assert_eq!(solved.problems.len(), SUBMISSIONS_PER_PAGE + 1);
+}
+
+#[tokio::test]
+async fn test_pages_fetched_before_a_failure_are_kept() {
+    let submissions = (0..3 * SUBMISSIONS_PER_PAGE)
+        .map(|i| (i as u64, format!("p{}", i), "AC"))
+        .collect();
+    let source = Arc::new(MockSource {
+        submissions: Mutex::new(submissions),
+        calls: Mutex::new(Vec::new()),
+        fails_after: Some(1),
+    });
+    let solved_problems = SolvedProblems::new(source.clone());
+
+    assert!(solved_problems.solved("alice").await.is_err());
+
+    // 失敗する前に取得したページの続きから取得し直す
+    assert!(solved_problems.solved("alice").await.is_err());
+    let next = (SUBMISSIONS_PER_PAGE - 1) as u64;
+    assert_eq!(*source.calls.lock().unwrap(), [0, next, next]);
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_for_the_same_user_share_one_fetch() {
+    let source = MockSource::new(vec![(10, "abc001_a", "AC")]);
+    let solved_problems = SolvedProblems::new(source.clone());
+
+    let (first, second) = tokio::join!(solved_problems.solved("alice"), solved_problems.solved("alice"));
+
+    assert!(first.unwrap().problems.contains("abc001_a"));
+    assert!(second.unwrap().problems.contains("abc001_a"));
+    assert_eq!(source.calls.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_cached_users_are_bounded() {
+    let solved_problems = SolvedProblems::new(MockSource::new(Vec::new()));
+
+    for i in 0..=MAX_CACHED_USERS {
+        solved_problems.solved(&format!("user{}", i)).await.unwrap();
+    }
+
+    assert_eq!(solved_problems.cached_users(), MAX_CACHED_USERS);
+}
+
+#[tokio::test]
+async fn test_user_parameter_excludes_solved_problems() {
+    let source = MockSource::new(vec![(10, "abc001_a", "AC"), (20, "abc001_b", "AC")]);
+    let (status, _, body) = send("/?user=alice&count=1", services_with(source)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let problems: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(problems[0]["id"], "abc001_c");
+}
+
+#[tokio::test]
+async fn test_partially_fetched_solved_problems_are_flagged() {
+    let submissions = (0..(MAX_PAGES_PER_REQUEST + 1) * SUBMISSIONS_PER_PAGE)
+        .map(|i| (i as u64, format!("p{}", i), "WA"))
+        .collect();
+    let source = Arc::new(MockSource {
+        submissions: Mutex::new(submissions),
+        calls: Mutex::new(Vec::new()),
+        fails_after: None,
+    });
+    let services = services_with(source);
+
+    let (status, headers, _) = send("/?user=alice", services.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[SOLVED_COMPLETE_HEADER], "false");
+
+    let (_, headers, _) = send("/?user=alice", services).await;
+    assert!(headers.get(SOLVED_COMPLETE_HEADER).is_none());
+}
+
+#[tokio::test]
+async fn test_user_who_solved_everything_gets_specific_message() {
+    let source = MockSource::new(vec![
+        (10, "abc001_a", "AC"),
+        (20, "abc001_b", "AC"),
+        (30, "abc001_c", "AC"),
+    ]);
+    let (status, _, body) = send("/?user=alice", services_with(source)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let err: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(err["code"], "solved_all");
+    assert_eq!(err["message"], "No problems match the conditions after excluding solved problems.");
+}
+
+#[tokio::test]
+async fn test_user_parameter_validation() {
+    let (status, _, body) = send("/?user=alice", Services::default()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let err: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(err["code"], "unsupported_parameter");
+    assert_eq!(err["param"], "user");
+    assert_eq!(err["message"], "'user' is not supported on this server.");
+
+    let source = MockSource::new(Vec::new());
+    let (status, _, body) = send("/?user=al%2Fice", services_with(source)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let err: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(err["code"], "invalid_value");
+    assert_eq!(err["message"], "'user' must be a valid AtCoder user ID.");
+}
+
+#[tokio::test]
+async fn test_source_failure_is_bad_gateway() {
+    let source = Arc::new(MockSource {
+        submissions: Mutex::new(Vec::new()),
+        calls: Mutex::new(Vec::new()),
+        fails_after: Some(0),
+    });
+    let (status, _, _) = send("/?user=alice", services_with(source)).await;
+
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+}
+
+#[tokio::test]
+async fn test_file_source_reads_user_submissions() {
+    let dir = std::env::temp_dir().join(format!("backend-submissions-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("alice.json"),
+        r#"[
+            {"id":1,"epoch_second":10,"problem_id":"abc001_a","contest_id":"abc001","user_id":"alice","result":"AC"},
+            {"id":2,"epoch_second":20,"problem_id":"abc001_b","contest_id":"abc001","user_id":"alice","result":"AC"}
+        ]"#,
+    )
+    .unwrap();
+
+    let source = FileSubmissionSource::new(dir.clone());
+    assert_eq!(source.fetch("alice", 0).await.unwrap().len(), 2);
+    assert_eq!(source.fetch("alice", 15).await.unwrap().len(), 1);
+    assert!(source.fetch("bob", 0).await.unwrap().is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_http_source_queries_user_submissions() {
+    let make_svc = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(|req: Request<Body>| async move {
+            let query = req.uri().query().unwrap_or("").to_string();
+            let body = if req.uri().path() == "/v3/user/submissions"
+                && query == "user=alice&from_second=5"
+            {
+                r#"[{"id":1,"epoch_second":10,"problem_id":"abc001_a","contest_id":"abc001","user_id":"alice","result":"AC"}]"#
+            } else {
+                "[]"
+            };
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        }))
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_svc);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+
+    let source = HttpSubmissionSource::new(&format!("http://{addr}/v3/")).unwrap();
+    let submissions = source.fetch("alice", 5).await.unwrap();
+
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].problem_id, "abc001_a");
+}
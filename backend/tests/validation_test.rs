@@ -0,0 +1,100 @@
+mod common;
+
+use backend::utils::api::{DataSource, Problem, ProblemModel};
+use backend::utils::reload::load_snapshot;
+use backend::utils::validation::{missing_data_files, validate_problems, ValidationReport};
+use std::collections::HashMap;
+use std::fs;
+
+use common::temp_data_dir;
+
+fn problem(id: &str) -> Problem {
+    Problem {
+        id: id.to_string(),
+        contest_id: "abc001".to_string(),
+        name: id.to_string(),
+    }
+}
+
+fn models(ids: &[&str]) -> HashMap<String, ProblemModel> {
+    ids.iter()
+        .map(|id| {
+            (
+                id.to_string(),
+                ProblemModel {
+                    difficulty: Some(400.0),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_consistent_data_is_valid() {
+    let report = validate_problems(
+        &[problem("abc001_a"), problem("abc001_b")],
+        &models(&["abc001_a", "abc001_b"]),
+    );
+
+    assert!(report.is_valid());
+}
+
+#[test]
+fn test_report_lists_each_kind_of_inconsistency() {
+    let report = validate_problems(
+        &[problem("abc001_a"), problem("abc001_a"), problem("abc001_b")],
+        &models(&["abc001_a", "abc001_c"]),
+    );
+
+    assert!(!report.is_valid());
+    assert_eq!(report.duplicated_problem_ids, ["abc001_a"]);
+    assert_eq!(report.problems_without_models, ["abc001_b"]);
+    assert_eq!(report.models_without_problems, ["abc001_c"]);
+    assert_eq!(
+        report.to_string(),
+        "problem data is invalid; duplicated problem ids (1): abc001_a; \
+         problems without models (1): abc001_b; models without problems (1): abc001_c"
+    );
+}
+
+#[test]
+fn test_missing_data_files_are_reported() {
+    let dir = temp_data_dir("validation", "missing");
+    fs::write(dir.join("problems.json"), "[]").unwrap();
+
+    let missing = missing_data_files(&dir);
+    assert_eq!(missing.len(), 1);
+    assert!(missing[0].ends_with("problem-models.json"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_strict_load_refuses_missing_files() {
+    let dir = temp_data_dir("validation", "strict");
+    let source = DataSource {
+        dir: dir.clone(),
+        strict: true,
+    };
+
+    let err = load_snapshot(&source).await.err().unwrap();
+    let report = err.downcast_ref::<ValidationReport>().unwrap();
+    assert_eq!(report.missing_files.len(), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_lenient_load_allows_missing_files() {
+    let dir = temp_data_dir("validation", "lenient");
+    let source = DataSource {
+        dir: dir.clone(),
+        strict: false,
+    };
+
+    let (state, _) = load_snapshot(&source).await.unwrap();
+    assert!(state.problems.is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
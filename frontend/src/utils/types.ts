@@ -3,6 +3,8 @@ export interface Problem {
 	contest_id: string;
 	name: string;
 	difficulty: number | null;
+	seed?: number;
+	url?: string;
 };
 
 export type ClosedRange = {